@@ -0,0 +1,57 @@
+use crate::BorderMode;
+
+/// 没有自定义地图时的场地形状
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Arena {
+    /// 只有边界墙
+    #[default]
+    Rectangle,
+    /// 中间一个十字,上下左右各留出通道
+    Cross,
+    /// 四个2x2的柱子
+    Pillars,
+}
+impl Arena {
+    /// 墙的坐标,thickness是边界墙的厚度,能穿过的边没有边界墙
+    pub(crate) fn walls(
+        &self,
+        width: usize,
+        height: usize,
+        thickness: usize,
+        border: BorderMode,
+    ) -> Vec<(usize, usize)> {
+        let mut walls = Vec::new();
+        for x in 0..width {
+            for y in 0..height {
+                if border.is_wall((x, y), (width, height), thickness) {
+                    walls.push((x, y));
+                }
+            }
+        }
+        let inner = match self {
+            Arena::Rectangle => Vec::new(),
+            Arena::Cross => {
+                let (cx, cy) = (width / 2, height / 2);
+                (width / 4..width * 3 / 4)
+                    .map(|x| (x, cy))
+                    .chain((height / 4..height * 3 / 4).map(|y| (cx, y)))
+                    .collect()
+            }
+            Arena::Pillars => [
+                (width / 4, height / 4),
+                (width * 3 / 4, height / 4),
+                (width / 4, height * 3 / 4),
+                (width * 3 / 4, height * 3 / 4),
+            ]
+            .into_iter()
+            .flat_map(|(x, y)| [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)])
+            .collect(),
+        };
+        for pos in inner {
+            if !walls.contains(&pos) {
+                walls.push(pos);
+            }
+        }
+        walls
+    }
+}
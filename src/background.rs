@@ -0,0 +1,37 @@
+use crossterm::style::{Color, StyledContent, Stylize};
+
+use crate::Theme;
+
+/// 棋盘格子隔一个用的底色
+const CHECKER: Color = Color::AnsiValue(234);
+
+/// 空格子的背景图案,有些终端上纯色不好看清位置
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum BackgroundStyle {
+    /// 纯色
+    #[default]
+    Solid,
+    /// 深浅相间的棋盘格
+    Checkerboard,
+    /// 隔一格一个点
+    Dotted,
+}
+impl BackgroundStyle {
+    /// (x, y)上空格子的样子,content是纯色时的样子
+    pub(crate) fn empty(
+        &self,
+        (x, y): (usize, usize),
+        content: StyledContent<char>,
+        theme: &Theme,
+    ) -> StyledContent<char> {
+        match self {
+            BackgroundStyle::Checkerboard if (x + y) % 2 == 1 => {
+                content.content().with(CHECKER).on(CHECKER)
+            }
+            BackgroundStyle::Dotted if (x + y) % 2 == 0 => {
+                '·'.with(Color::DarkGrey).on(theme.background)
+            }
+            _ => content,
+        }
+    }
+}
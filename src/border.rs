@@ -0,0 +1,70 @@
+/// 棋盘的一条边
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Edge {
+    /// 有墙,撞上就死
+    #[default]
+    Wall,
+    /// 没有墙,从这边出去从对边进来
+    Wrap,
+}
+/// 四条边各自是墙还是可以穿过,默认四面都是墙
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct BorderMode {
+    pub top: Edge,
+    pub bottom: Edge,
+    pub left: Edge,
+    pub right: Edge,
+}
+impl BorderMode {
+    /// 四面都是墙
+    pub fn walls() -> Self {
+        BorderMode::default()
+    }
+    /// 四面都能穿过,就是穿墙模式
+    pub fn wrap() -> Self {
+        BorderMode {
+            top: Edge::Wrap,
+            bottom: Edge::Wrap,
+            left: Edge::Wrap,
+            right: Edge::Wrap,
+        }
+    }
+    /// 左右穿过,上下是墙
+    pub fn horizontal_wrap() -> Self {
+        BorderMode {
+            left: Edge::Wrap,
+            right: Edge::Wrap,
+            ..BorderMode::default()
+        }
+    }
+    /// (x, y)离有墙的边不到thickness格
+    pub(crate) fn is_wall(
+        &self,
+        (x, y): (usize, usize),
+        (width, height): (usize, usize),
+        thickness: usize,
+    ) -> bool {
+        (self.left == Edge::Wall && x < thickness)
+            || (self.right == Edge::Wall && width - 1 - x < thickness)
+            || (self.top == Edge::Wall && y < thickness)
+            || (self.bottom == Edge::Wall && height - 1 - y < thickness)
+    }
+    /// 走出能穿过的边时换到对边,出了有墙的边不变
+    pub(crate) fn wrap_position(
+        &self,
+        (x, y): (isize, isize),
+        (width, height): (isize, isize),
+    ) -> (isize, isize) {
+        let x = match x {
+            x if x < 0 && self.left == Edge::Wrap => width - 1,
+            x if x >= width && self.right == Edge::Wrap => 0,
+            x => x,
+        };
+        let y = match y {
+            y if y < 0 && self.top == Edge::Wrap => height - 1,
+            y if y >= height && self.bottom == Edge::Wrap => 0,
+            y => y,
+        };
+        (x, y)
+    }
+}
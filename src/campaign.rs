@@ -0,0 +1,93 @@
+use std::io::Write;
+
+use crate::{map::Map, CellType, Direction, Game, GameError, Snack, StepResult};
+
+/// 关卡模式每一关要多拿的分数
+pub(crate) const LEVEL_SCORE: usize = 20;
+
+impl<W: Write> Game<W> {
+    /// 关卡模式中分数够了就进入下一关,最后一关也过了返回`StepResult::Won`
+    pub(crate) fn check_level_up(&mut self) -> Result<Option<StepResult>, GameError> {
+        if self.levels.is_empty() || self.score < (self.level + 1) * LEVEL_SCORE {
+            return Ok(None);
+        }
+        if self.level + 1 == self.levels.len() {
+            return Ok(Some(StepResult::Won));
+        }
+        self.level += 1;
+        self.use_level_layout();
+        //换地图后重新布置整个棋盘,分数保留
+        for cell in self.cells.iter_mut().flatten() {
+            cell.cell_type = CellType::Empty;
+            cell.faded = false;
+            cell.highlighted = false;
+            cell.invalidate();
+        }
+        self.dirty.clear();
+        self.snack = self.initial_snack.clone();
+        self.foods.clear();
+        self.food_distances.clear();
+        self.food_points.clear();
+        self.bonus = None;
+        self.poison = None;
+        self.runner = None;
+        self.trail.clear();
+        self.grace_wall = None;
+        self.wrap_flash = None;
+        self.warned_wall = None;
+        self.reset_shrink();
+        self.pending_growth = 0;
+        self.move_hint = None;
+        self.tail_cell = None;
+        self.delayed_growth.clear();
+        self.input_queue.clear();
+        self.input_key = None;
+        self.last_wrap = None;
+        if let Some(rival) = self.rival.as_mut() {
+            rival.reset();
+        }
+        self.build_default()?;
+        self.render_all()?;
+        self.print_title()?;
+        self.print_score()?;
+        Ok(Some(StepResult::LevelUp))
+    }
+    /// 墙、刀刃、传送门和开局的蛇换成当前关卡的
+    pub(crate) fn use_level_layout(&mut self) {
+        let Some(map) = self.levels.get(self.level) else {
+            return;
+        };
+        self.map_walls = Some(map.walls.clone());
+        self.map_blades = map.blades.clone();
+        self.portals = map.portals.clone();
+        self.initial_snack = Snack {
+            direction: Direction::Right,
+            moved: Direction::Right,
+            head: map.start,
+            bodys: Default::default(),
+        };
+    }
+    /// 关卡模式中当前是第几关(从1开始)和总关数,不是关卡模式返回None
+    pub fn level(&self) -> Option<(usize, usize)> {
+        (!self.levels.is_empty()).then_some((self.level + 1, self.levels.len()))
+    }
+}
+/// 解析所有关卡,尺寸都要和第一关相同
+pub(crate) fn parse_levels(levels: &[String]) -> Result<Vec<Map>, GameError> {
+    let maps = levels
+        .iter()
+        .map(|level| Map::parse(level))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(first) = maps.first() {
+        if let Some(i) = maps
+            .iter()
+            .position(|map| (map.width, map.height) != (first.width, first.height))
+        {
+            return Err(GameError::InvalidMap(format!(
+                "第{}关的尺寸和第一关不同",
+                i + 1
+            )));
+        }
+    }
+    Ok(maps)
+}
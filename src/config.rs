@@ -0,0 +1,967 @@
+use std::{
+    fs::File,
+    io::{sink, stdout, BufWriter, Sink, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crossterm::terminal;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
+use crate::{
+    campaign::parse_levels,
+    daily::{daily_seed, today, DAILY_SIZE},
+    ghost::load_ghost,
+    leaderboard::load_leaderboard,
+    levels::builtin_level,
+    map::Map,
+    panel::{PANEL_GAP, PANEL_ROWS, PANEL_WIDTH},
+    rival::Rival,
+    save::load_save,
+    Arena, AutoSize, BackgroundStyle, BorderMode, ControlScheme, Direction, FoodDecay, Game,
+    GameError, Glyphs, HeadOn, InputPriority, KeyBindings, MultiFoodBonus, OpeningInput, Snack,
+    SpeedCurve, Theme, BLADE_MIN_LENGTH, BONUS_CHANCE, COUNTDOWN_STEP, INITIAL_SPEED, MIN_HEIGHT,
+};
+
+/// 游戏配置,所有可调的参数都在这里,如`GameConfig::new().speed(60).wrap(true).seed(42).build()`
+#[derive(Clone, Debug)]
+pub struct GameConfig {
+    size: Option<(usize, usize)>,
+    auto_size: Option<AutoSize>,
+    map: Option<String>,
+    level: Option<String>,
+    levels: Vec<String>,
+    arena: Arena,
+    border_thickness: usize,
+    speed: u64,
+    border: BorderMode,
+    wrap_cost: usize,
+    wall_hug_every: Option<u64>,
+    seed: Option<u64>,
+    daily: Option<String>,
+    lives: usize,
+    wall_costs_life: bool,
+    mercy_frames: u64,
+    wall_grace: bool,
+    catch_up_frames: usize,
+    first_food_delay: u64,
+    food_decay: Option<FoodDecay>,
+    food_lifetime: Option<(u64, usize)>,
+    food_values: Option<(usize, usize)>,
+    food_wall_clearance: usize,
+    food_head_clearance: usize,
+    target_score: Option<usize>,
+    target_length: Option<usize>,
+    bonus_chance: f64,
+    runner_every: Option<u64>,
+    speed_curve: Option<SpeedCurve>,
+    time_limit: Option<Duration>,
+    initial_snake: Option<((usize, usize), usize, Direction)>,
+    initial_direction: Option<Direction>,
+    pre_grown_snake: Option<usize>,
+    track_slow_frames: bool,
+    multi_food_bonus: Option<MultiFoodBonus>,
+    move_hints: bool,
+    distinct_tail: bool,
+    max_input_age_ticks: Option<u64>,
+    input_priority: InputPriority,
+    blink_warning: Option<u64>,
+    blades: Vec<(usize, usize)>,
+    blade_min_length: usize,
+    lap_bonus: usize,
+    theme: Theme,
+    glyphs: Glyphs,
+    blink_food: bool,
+    background: BackgroundStyle,
+    countdown: (usize, Duration),
+    hazard_every: Option<usize>,
+    shrink_every: Option<Duration>,
+    self_collision: bool,
+    tail_chasing: bool,
+    invert_controls: bool,
+    body_gradient: bool,
+    assist: bool,
+    ruler: bool,
+    no_color: bool,
+    edge_warning: bool,
+    reduced_animation: bool,
+    wait_for_input: bool,
+    opening_input: OpeningInput,
+    alternate_screen: bool,
+    frame_log: Option<PathBuf>,
+    autosave: Option<(PathBuf, Duration)>,
+    leaderboard: Option<PathBuf>,
+    minimap: bool,
+    square_cells: bool,
+    hud_below: bool,
+    big_hud: bool,
+    autopilot: bool,
+    food_count: usize,
+    initial_food: usize,
+    growth_per_food: usize,
+    growth_delay: u64,
+    speed_keys: bool,
+    confirm_quit: bool,
+    menu: bool,
+    sound: bool,
+    ghost_trail: bool,
+    ghost_run: Option<PathBuf>,
+    key_bindings: KeyBindings,
+    two_player: bool,
+    bigger_eats_smaller: bool,
+    head_on: HeadOn,
+}
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            size: None,
+            auto_size: None,
+            map: None,
+            level: None,
+            levels: Vec::new(),
+            arena: Arena::Rectangle,
+            border_thickness: 1,
+            speed: INITIAL_SPEED,
+            border: BorderMode::walls(),
+            wrap_cost: 0,
+            wall_hug_every: None,
+            seed: None,
+            daily: None,
+            lives: 1,
+            wall_costs_life: false,
+            mercy_frames: 0,
+            wall_grace: false,
+            catch_up_frames: 0,
+            first_food_delay: 0,
+            food_decay: None,
+            food_lifetime: None,
+            food_values: None,
+            food_wall_clearance: 0,
+            food_head_clearance: 0,
+            target_score: None,
+            target_length: None,
+            bonus_chance: BONUS_CHANCE,
+            runner_every: None,
+            speed_curve: None,
+            time_limit: None,
+            initial_snake: None,
+            initial_direction: None,
+            pre_grown_snake: None,
+            track_slow_frames: false,
+            multi_food_bonus: None,
+            move_hints: false,
+            distinct_tail: false,
+            max_input_age_ticks: None,
+            input_priority: InputPriority::Buffered,
+            blink_warning: None,
+            blades: Vec::new(),
+            blade_min_length: BLADE_MIN_LENGTH,
+            lap_bonus: 0,
+            theme: Theme::classic(),
+            glyphs: Glyphs::block(),
+            blink_food: true,
+            background: BackgroundStyle::Solid,
+            countdown: (3, COUNTDOWN_STEP),
+            hazard_every: None,
+            shrink_every: None,
+            self_collision: true,
+            tail_chasing: true,
+            invert_controls: false,
+            body_gradient: false,
+            assist: false,
+            ruler: false,
+            //约定设置了NO_COLOR环境变量就不输出颜色
+            no_color: std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()),
+            edge_warning: false,
+            reduced_animation: false,
+            wait_for_input: false,
+            opening_input: OpeningInput::LastDirection,
+            alternate_screen: true,
+            frame_log: None,
+            autosave: None,
+            leaderboard: None,
+            minimap: false,
+            square_cells: false,
+            hud_below: false,
+            big_hud: false,
+            autopilot: false,
+            food_count: 1,
+            initial_food: 1,
+            growth_per_food: 1,
+            growth_delay: 0,
+            speed_keys: true,
+            confirm_quit: false,
+            menu: false,
+            sound: false,
+            ghost_trail: false,
+            ghost_run: None,
+            key_bindings: KeyBindings::classic(),
+            two_player: false,
+            bigger_eats_smaller: false,
+            head_on: HeadOn::BothDie,
+        }
+    }
+}
+impl GameConfig {
+    /// 默认配置,和`Game::new()`相同
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// 棋盘尺寸,不设置时铺满终端
+    pub fn size(mut self, width: usize, height: usize) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+    /// 按宽高比自动选择棋盘尺寸并居中,优先于`size`
+    pub fn auto_size(mut self, auto: AutoSize) -> Self {
+        self.auto_size = Some(auto);
+        self
+    }
+    /// 文本地图,`#`是墙,`.`是空地,`S`是蛇头起点,`X`是刀刃,优先于其他尺寸设置
+    pub fn map(mut self, map: &str) -> Self {
+        self.map = Some(map.to_string());
+        self
+    }
+    /// 内置的命名关卡,classic、donut、cross或spiral,见`level_names`,优先于`map`,在终端中居中,
+    /// 没有这个名字时创建失败
+    pub fn level(mut self, name: &str) -> Self {
+        self.level = Some(name.to_string());
+        self
+    }
+    /// 关卡模式,按顺序的文本地图,每关多拿20分进入下一关,分数保留,
+    /// 所有地图尺寸必须相同,优先于`map`
+    pub fn levels(mut self, levels: Vec<String>) -> Self {
+        self.levels = levels;
+        self
+    }
+    /// 场地形状,默认只有边界墙,设置了自定义地图时不起作用
+    pub fn arena(mut self, arena: Arena) -> Self {
+        self.arena = arena;
+        self
+    }
+    /// 边界墙的厚度,至少1格,穿墙模式没有边界墙
+    pub fn border_thickness(mut self, thickness: usize) -> Self {
+        self.border_thickness = thickness.max(1);
+        self
+    }
+    /// 开局速度,每帧间隔毫秒数
+    pub fn speed(mut self, ms: u64) -> Self {
+        self.speed = ms;
+        self
+    }
+    /// 按分数决定速度的加速曲线,比如`SpeedCurve::gentle()`,代替每吃一个食物快一点
+    pub fn speed_curve(mut self, curve: SpeedCurve) -> Self {
+        self.speed_curve = Some(curve);
+        self
+    }
+    /// 能否在游戏中用+/-手动调速,默认可以
+    pub fn speed_keys(mut self, enable: bool) -> Self {
+        self.speed_keys = enable;
+        self
+    }
+    /// 按退出键时先暂停并询问,按y退出,按n继续,默认直接退出
+    pub fn confirm_quit(mut self, enable: bool) -> Self {
+        self.confirm_quit = enable;
+        self
+    }
+    /// 穿墙模式,四面都能穿过,会替换之前设置的边界
+    pub fn wrap(mut self, wrap: bool) -> Self {
+        self.border = if wrap {
+            BorderMode::wrap()
+        } else {
+            BorderMode::walls()
+        };
+        self
+    }
+    /// 穿墙扣分模式,四面都能穿过,但每穿一次扣points分,扣到0为止,穿过的地方会闪一下
+    pub fn wrap_with_cost(mut self, points: usize) -> Self {
+        self.border = BorderMode::wrap();
+        self.wrap_cost = points;
+        self
+    }
+    /// 贴墙奖励,蛇头紧挨着墙走every帧加1分,分数栏显示这一局贴墙加了多少,默认不加;
+    /// 吃一个食物才1分,every设成10左右比较合适,太小了绕墙转圈比吃食物还划算
+    pub fn wall_hugging(mut self, every: u64) -> Self {
+        self.wall_hug_every = Some(every.max(1));
+        self
+    }
+    /// 分别设置四条边是墙还是能穿过,比如左右穿过、上下撞死
+    pub fn border(mut self, border: BorderMode) -> Self {
+        self.border = border;
+        self
+    }
+    /// 穿墙模式下同一方向连穿两次墙,也就是横穿或纵穿了一整圈,奖励points分,默认0不奖励
+    pub fn lap_bonus(mut self, points: usize) -> Self {
+        self.lap_bonus = points;
+        self
+    }
+    /// 随机数种子,相同种子生成相同的食物序列
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+    /// 今天的每日挑战,种子按`daily_seed`由今天的日期算出,棋盘固定是`DAILY_SIZE`,
+    /// 不管`size`和`auto_size`,分数栏显示日期
+    pub fn daily(mut self) -> Self {
+        let (year, month, day) = today();
+        self.seed = Some(daily_seed(year, month, day));
+        self.daily = Some(format!("{:04}-{:02}-{:02}", year, month, day));
+        self
+    }
+    /// 每局的命数,至少1条
+    pub fn lives(mut self, lives: usize) -> Self {
+        self.lives = lives.max(1);
+        self
+    }
+    /// 撞墙是否也只扣一条命,默认撞墙直接结束
+    pub fn wall_costs_life(mut self, enable: bool) -> Self {
+        self.wall_costs_life = enable;
+        self
+    }
+    /// 复活后无敌frames帧,期间撞墙停下不死、穿过自己也不死,蛇一直闪烁,照常能吃食物,默认0
+    pub fn mercy_frames(mut self, frames: u64) -> Self {
+        self.mercy_frames = frames;
+        self
+    }
+    /// 宽限模式,第一次撞墙时停一下并闪烁那面墙,下一帧还朝墙走才算撞上,撞自己不宽限
+    pub fn wall_grace(mut self, enable: bool) -> Self {
+        self.wall_grace = enable;
+        self
+    }
+    /// 渲染太慢落后于帧间隔时,最多连续推进几帧逻辑再渲染一次,让移动速度跟上真实时间,
+    /// 落后更多的帧直接丢掉;默认0,卡顿时不补帧,游戏整体变慢
+    pub fn catch_up_frames(mut self, frames: usize) -> Self {
+        self.catch_up_frames = frames;
+        self
+    }
+    /// 开局多少帧后才出现第一个食物
+    pub fn first_food_delay(mut self, ticks: u64) -> Self {
+        self.first_food_delay = ticks;
+        self
+    }
+    /// 同时存在的食物数量,至少1个
+    pub fn food_count(mut self, count: usize) -> Self {
+        self.food_count = count.max(1);
+        self
+    }
+    /// 开局放几个食物,至少1个,棋盘放不下时有几个放几个;
+    /// 比`food_count`多时吃掉的先不补,少于`food_count`时按`food_count`放
+    pub fn initial_food(mut self, count: usize) -> Self {
+        self.initial_food = count.max(1);
+        self
+    }
+    /// 吃一个食物长几节,至少1节,多出来的在之后几帧长出来
+    pub fn growth_per_food(mut self, growth: usize) -> Self {
+        self.growth_per_food = growth.max(1);
+        self
+    }
+    /// 吃到食物后蛇先不变长,过frames帧才从尾巴长出来,像食物要在肚子里走一段;默认0,吃的这一帧就长
+    pub fn growth_delay(mut self, frames: u64) -> Self {
+        self.growth_delay = frames;
+        self
+    }
+    /// 食物分值衰减
+    pub fn food_decay(mut self, decay: FoodDecay) -> Self {
+        self.food_decay = Some(decay);
+        self
+    }
+    /// 食物放frames帧没被吃掉就烂掉,在别处重新生成,每烂一个扣penalty分,frames至少1
+    pub fn food_lifetime(mut self, frames: u64, penalty: usize) -> Self {
+        self.food_lifetime = Some((frames.max(1), penalty));
+        self
+    }
+    /// 每个食物生成时在min到max之间随机一个分值,食物上显示分数,和分值衰减同时设置时按衰减算
+    pub fn food_values(mut self, min: usize, max: usize) -> Self {
+        let min = min.max(1);
+        self.food_values = Some((min, max.max(min)));
+        self
+    }
+    /// 食物与墙之间至少隔开的格数,默认0;设成1时食物不会贴着墙和墙角生成,
+    /// 离墙够远的空地都被占了才放宽,快满的棋盘也放得下
+    pub fn food_wall_clearance(mut self, distance: usize) -> Self {
+        self.food_wall_clearance = distance;
+        self
+    }
+    /// 按食物生成时离蛇头远近给的额外奖励,场上只有一个食物时不加,默认不奖励
+    pub fn multi_food_bonus(mut self, bonus: MultiFoodBonus) -> Self {
+        self.multi_food_bonus = Some(bonus);
+        self
+    }
+    /// 新食物不放在蛇头正前方distance格内,给玩家留出反应时间,没有别的空位时不限制,默认0
+    pub fn food_head_clearance(mut self, distance: usize) -> Self {
+        self.food_head_clearance = distance;
+        self
+    }
+    /// 竞速模式,尽快拿到目标分数,达到后结束并返回用时
+    pub fn target_score(mut self, score: usize) -> Self {
+        self.target_score = Some(score);
+        self
+    }
+    /// 蛇长到length节(包括蛇头)就赢,分数栏显示当前长度
+    pub fn target_length(mut self, length: usize) -> Self {
+        self.target_length = Some(length);
+        self
+    }
+    /// 吃到食物后出现奖励食物的概率,0到1之间,0不出现,1每次都出现,默认0.2
+    pub fn bonus_chance(mut self, chance: f64) -> Self {
+        self.bonus_chance = chance;
+        self
+    }
+    /// 棋盘上多一个会跑的食物,每隔every帧往旁边挪一格,吃到和奖励食物一样加分,0表示没有
+    pub fn runner_food(mut self, every: u64) -> Self {
+        self.runner_every = (every > 0).then_some(every);
+        self
+    }
+    /// 限时模式,时间到了游戏正常结束
+    pub fn time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+    /// 开局的蛇:蛇头位置、总长度(包括蛇头)和朝向,蛇身沿朝向的反方向排成直线
+    pub fn initial_snake(
+        mut self,
+        head: (usize, usize),
+        length: usize,
+        direction: Direction,
+    ) -> Self {
+        self.initial_snake = Some((head, length, direction));
+        self
+    }
+    /// 开局的蛇改成棋盘正中长8节的直线,蛇身沿朝向的反方向排开,四个方向都不会贴着墙,
+    /// 设置了`initial_snake`时不起作用
+    pub fn initial_direction(mut self, direction: Direction) -> Self {
+        self.initial_direction = Some(direction);
+        self
+    }
+    /// 开局就是length节(包括蛇头)的长蛇,从左上角开始来回一行行铺满,不用玩几分钟
+    /// 就能测长蛇的渲染和碰撞;比`initial_snake`优先,棋盘放不下时创建失败
+    pub fn pre_grown_snake(mut self, length: usize) -> Self {
+        self.pre_grown_snake = Some(length);
+        self
+    }
+    /// 统计逻辑加渲染耗时超过一帧时长的帧数
+    pub fn track_slow_frames(mut self, enable: bool) -> Self {
+        self.track_slow_frames = enable;
+        self
+    }
+    /// 教学模式,每帧在自动驾驶建议走的下一格上画一个淡色提示,玩家照常自己操作,默认关闭
+    pub fn move_hints(mut self, enable: bool) -> Self {
+        self.move_hints = enable;
+        self
+    }
+    /// 蛇尾用单独的字符和颜色显示,看得出蛇往哪边走,默认关闭
+    pub fn distinct_tail(mut self, enable: bool) -> Self {
+        self.distinct_tail = enable;
+        self
+    }
+    /// 输入缓冲里的转向超过这么多帧还没轮到就丢掉,免得很久以前的按键突然生效,默认不限制
+    pub fn max_input_age_ticks(mut self, ticks: u64) -> Self {
+        self.max_input_age_ticks = Some(ticks);
+        self
+    }
+    /// 同一帧里按了几个转向键时的处理方式,默认都放进输入缓冲
+    pub fn input_priority(mut self, priority: InputPriority) -> Self {
+        self.input_priority = priority;
+        self
+    }
+    /// 食物刚出现时不闪,离奖励食物消失或普通食物烂掉不到frames帧时才开始闪烁提醒,
+    /// 不会烂的普通食物一直不闪;默认一直闪烁
+    pub fn blink_before_expiry(mut self, frames: u64) -> Self {
+        self.blink_warning = Some(frames);
+        self
+    }
+    /// 在这些格子上放刀刃,和地图里的`X`一样,出了棋盘时创建失败
+    pub fn blades(mut self, blades: Vec<(usize, usize)>) -> Self {
+        self.blades = blades;
+        self
+    }
+    /// 被刀刃砍断后蛇至少要剩几节(包括蛇头),不够就结束,默认3
+    pub fn blade_min_length(mut self, length: usize) -> Self {
+        self.blade_min_length = length;
+        self
+    }
+    /// 配色,默认`Theme::classic()`
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+    /// 格子显示的字符,默认`Glyphs::block()`
+    pub fn glyphs(mut self, glyphs: Glyphs) -> Self {
+        self.glyphs = glyphs;
+        self
+    }
+    /// 食物是否闪烁,默认闪烁,不支持闪烁的终端可能显示乱码,可以关掉
+    pub fn blink_food(mut self, enable: bool) -> Self {
+        self.blink_food = enable;
+        self
+    }
+    /// 开局在棋盘中间倒数steps下,每下step,最后显示开始,默认3下各0.5秒,0下不倒数直接开始
+    pub fn countdown(mut self, steps: usize, step: Duration) -> Self {
+        self.countdown = (steps, step);
+        self
+    }
+    /// 缩圈模式,每隔every最外一圈空地变成墙,场地越来越小,暂停时不计时
+    pub fn shrinking_arena(mut self, every: Duration) -> Self {
+        self.shrink_every = Some(every);
+        self
+    }
+    /// 倒数后蛇先不动,按了方向键才朝那边开始走,开局走神不会直接撞墙
+    pub fn wait_for_input(mut self, enable: bool) -> Self {
+        self.wait_for_input = enable;
+        self
+    }
+    /// 倒数时和开局前按的方向键怎么处理,默认倒数时最后按的方向作为开局方向;
+    /// 设成`OpeningInput::Ignore`时都丢掉,第一帧一定按原来的方向走
+    pub fn opening_input(mut self, opening: OpeningInput) -> Self {
+        self.opening_input = opening;
+        self
+    }
+    /// 运行时切到备用屏幕,默认开;关掉时直接画在当前屏幕上,退出后最后的画面留在滚动记录里,
+    /// 方便截图和在日志里看
+    pub fn alternate_screen(mut self, enable: bool) -> Self {
+        self.alternate_screen = enable;
+        self
+    }
+    /// 无尽模式,分数每涨every分在空地上加一段障碍墙,越来越挤,0表示不加
+    pub fn hazards(mut self, every: usize) -> Self {
+        self.hazard_every = (every > 0).then_some(every);
+        self
+    }
+    /// 撞到自己是否会死,关掉就是练习模式,蛇从自己身上穿过去,撞墙照样会死
+    pub fn self_collision(mut self, enable: bool) -> Self {
+        self.self_collision = enable;
+        self
+    }
+    /// 反向操作挑战,玩家一按的方向反过来,按左往右转,按上往下转,分数栏会提示
+    pub fn invert_controls(mut self, enable: bool) -> Self {
+        self.invert_controls = enable;
+        self
+    }
+    /// 蛇头能否走进这一帧正好要空出来的蛇尾,默认可以,和经典贪吃蛇一样,关掉时撞上蛇尾也会死
+    pub fn tail_chasing(mut self, enable: bool) -> Self {
+        self.tail_chasing = enable;
+        self
+    }
+    /// 每隔every自动把局面和统计存到path,暂停时不存,默认不自动存档
+    pub fn autosave<P: AsRef<Path>>(mut self, path: P, every: Duration) -> Self {
+        self.autosave = Some((path.as_ref().to_path_buf(), every));
+        self
+    }
+    /// 排行榜文件,记前10名的分数、名字和日期,一局结束分数能上榜时输入名字,
+    /// 结束面板上显示前5名,文件不存在或坏了就从空的排行榜开始
+    pub fn leaderboard<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.leaderboard = Some(path.as_ref().to_path_buf());
+        self
+    }
+    /// 和上一局的自己比:每局结束把蛇头的路线存到path,下一局同一时刻用变暗的格子显示上一局蛇头的位置,
+    /// 幽灵不挡路也不能吃;要固定种子,种子不同的路线不显示
+    pub fn ghost_run<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.ghost_run = Some(path.as_ref().to_path_buf());
+        self
+    }
+    /// 把每一帧的蛇头、方向、前方格子、分数和速度写到文件,方便复现奇怪的死法,默认不写
+    pub fn frame_log<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.frame_log = Some(path.as_ref().to_path_buf());
+        self
+    }
+    /// 蛇头下一格就是墙时把那面墙高亮提醒,不改变规则,默认关闭
+    pub fn edge_warning(mut self, enable: bool) -> Self {
+        self.edge_warning = enable;
+        self
+    }
+    /// 减少动画,死了直接显示结束面板,不播放蛇身变红的动画
+    pub fn reduced_animation(mut self, enable: bool) -> Self {
+        self.reduced_animation = enable;
+        self
+    }
+    /// 蛇身按离蛇头的远近由亮变暗,默认整条蛇一个颜色
+    pub fn body_gradient(mut self, enable: bool) -> Self {
+        self.body_gradient = enable;
+        self
+    }
+    /// 在上边的墙上显示列号、左边的墙上显示行号,逢十显示十位,方便设计地图时对齐坐标,
+    /// 只是显示,不影响碰撞和食物,那一边不是墙或缩小显示时不显示,默认关闭
+    pub fn ruler(mut self, enable: bool) -> Self {
+        self.ruler = enable;
+        self
+    }
+    /// 不输出颜色和样式,只输出字符,适合不支持颜色的终端、CI日志和管道,
+    /// 没有自定义字符时换成`Glyphs::ascii()`,默认在设置了`NO_COLOR`环境变量时开启
+    pub fn no_color(mut self, enable: bool) -> Self {
+        self.no_color = enable;
+        self
+    }
+    /// 空格子的背景图案,默认纯色
+    pub fn background(mut self, style: BackgroundStyle) -> Self {
+        self.background = style;
+        self
+    }
+    /// 棋盘比终端大时缩小显示,一个字符代表一块格子,默认不开启,棋盘太大会报错
+    pub fn minimap(mut self, enable: bool) -> Self {
+        self.minimap = enable;
+        self
+    }
+    /// 每格画成两个字符宽,终端字符高是宽的两倍,这样棋盘看起来是方的,默认关闭
+    pub fn square_cells(mut self, enable: bool) -> Self {
+        self.square_cells = enable;
+        self
+    }
+    /// 终端在棋盘下面还有一行时把分数栏画在那里,不挡住棋盘最后一行,按终端选尺寸时会留出这一行
+    pub fn hud_below(mut self, enable: bool) -> Self {
+        self.hud_below = enable;
+        self
+    }
+    /// 直播用的大分数面板,在棋盘右边用大块显示得分、最高分、速度、长度和用时,分数栏照常显示;
+    /// 终端在棋盘右边放不下面板时只有分数栏,默认关闭
+    pub fn big_hud(mut self, enable: bool) -> Self {
+        self.big_hud = enable;
+        self
+    }
+    /// 高对比度模式,格子用不同的ASCII字符区分,会替换之前设置的配色和字符
+    pub fn high_contrast(mut self, enable: bool) -> Self {
+        (self.theme, self.glyphs) = if enable {
+            (Theme::high_contrast(), Glyphs::ascii())
+        } else {
+            (Theme::classic(), Glyphs::block())
+        };
+        self
+    }
+    /// 辅助模式,某一帧没按方向键时蛇自动朝最近的食物拐,不会拐到墙上或自己身上,按了键以玩家为准
+    pub fn assist(mut self, enable: bool) -> Self {
+        self.assist = enable;
+        self
+    }
+    /// 自动驾驶演示模式,蛇自己找食物
+    pub fn autopilot(mut self, enable: bool) -> Self {
+        self.autopilot = enable;
+        self
+    }
+    /// 吃食物时响一声铃,死亡时响两声,默认关闭
+    pub fn sound(mut self, enable: bool) -> Self {
+        self.sound = enable;
+        self
+    }
+    /// 蛇尾离开的格子短暂留下变暗的残影
+    pub fn ghost_trail(mut self, enable: bool) -> Self {
+        self.ghost_trail = enable;
+        self
+    }
+    /// 按键绑定,创建时检查有没有重复的键
+    pub fn key_bindings(mut self, bindings: KeyBindings) -> Self {
+        self.key_bindings = bindings;
+        self
+    }
+    /// 选一套常用的操作键,会替换掉之前设置的按键绑定,默认`ControlScheme::Wasd`
+    pub fn control_scheme(mut self, scheme: ControlScheme) -> Self {
+        self.key_bindings = scheme.bindings();
+        self
+    }
+    /// 双人模式,玩家二在右侧用方向键控制,每人一条命,先死的输;
+    /// 没有自定义按键时换成`KeyBindings::two_player()`
+    pub fn two_player(mut self, enable: bool) -> Self {
+        self.two_player = enable;
+        self
+    }
+    /// 双人模式的大吃小规则:蛇头撞到对方蛇身时比长度,长的一方活下来,对方死,
+    /// 不比对方长时还是撞人的死;默认关闭,谁撞上谁死
+    pub fn bigger_eats_smaller(mut self, enable: bool) -> Self {
+        self.bigger_eats_smaller = enable;
+        self
+    }
+    /// 双人模式两个蛇头同一帧撞进同一格时的规则,在其他碰撞检查之前处理,默认同归于尽
+    pub fn head_on(mut self, rule: HeadOn) -> Self {
+        self.head_on = rule;
+        self
+    }
+    /// 开局前显示开始菜单,选择模式和难度,会覆盖穿墙、限时和速度的设置
+    pub fn menu(mut self, enable: bool) -> Self {
+        self.menu = enable;
+        self
+    }
+    /// 按配置创建输出到终端的游戏
+    pub fn build(self) -> Result<Game, GameError> {
+        let (term_w, term_h) = terminal::size().map_err(|_| GameError::TerminalSizeUnavailable)?;
+        let (columns, term_h) = (term_w as usize, term_h as usize);
+        //方格显示时一格占两列,按格数算终端放得下多少
+        let cell_width = if self.square_cells { 2 } else { 1 };
+        let term_w = columns / cell_width;
+        //分数栏放在棋盘下面时,按终端选的尺寸留出一行,留了就太矮的话不留
+        let fit_h = if self.hud_below && term_h > MIN_HEIGHT {
+            term_h - 1
+        } else {
+            term_h
+        };
+        let map = self.first_map()?;
+        let (width, height) = if let Some(map) = &map {
+            if self.level.is_some() && (map.width > term_w || map.height > term_h) {
+                return Err(GameError::BoardLargerThanTerminal);
+            }
+            (map.width, map.height)
+        } else if let (Some(auto), None) = (self.auto_size, &self.daily) {
+            auto.fit(term_w, fit_h)?
+        } else if let Some((width, height)) = self.fixed_size() {
+            if !self.minimap && (width > term_w || height > term_h) {
+                return Err(GameError::BoardLargerThanTerminal);
+            }
+            (width, height)
+        } else {
+            (term_w, fit_h)
+        };
+        //放不下时按终端大小分块,每块显示成一个字符
+        let zoom = (self.minimap && (width > term_w || height > term_h)).then(|| {
+            (
+                width.div_ceil(term_w.max(1)),
+                height.div_ceil(term_h.max(1)),
+            )
+        });
+        let (shown_w, shown_h) = zoom.map_or((width, height), |(block_w, block_h)| {
+            (width.div_ceil(block_w), height.div_ceil(block_h))
+        });
+        //下面没有多余的一行时分数栏还是画在棋盘最后一行
+        let hud_below = self.hud_below && shown_h < term_h;
+        //棋盘比终端小时居中,棋盘和分数栏都按这个偏移绘制
+        let mut offset = (
+            columns.saturating_sub(shown_w * cell_width) as u16 / 2,
+            term_h.saturating_sub(shown_h + usize::from(hud_below)) as u16 / 2,
+        );
+        //大分数面板放在棋盘右边,放得下时棋盘和面板一起居中,放不下只有分数栏
+        let board_w = shown_w * cell_width;
+        let needed_w = board_w + PANEL_GAP + PANEL_WIDTH;
+        let panel = (self.big_hud && columns >= needed_w && shown_h >= PANEL_ROWS).then(|| {
+            offset.0 = ((columns - needed_w) / 2) as u16;
+            (offset.0 + (board_w + PANEL_GAP) as u16, offset.1)
+        });
+        let snack = self.start_snake(map.as_ref(), (width, height))?;
+        let game = Game::build(stdout(), width, height, offset, snack)?;
+        let mut game = self.apply(game, map)?;
+        game.zoom = zoom;
+        game.hud_below = hud_below;
+        game.panel = panel;
+        Ok(game)
+    }
+    /// 按配置创建输出到任意writer的游戏,不检查终端尺寸,方便测试
+    pub fn build_with_writer<W: Write>(self, writer: W) -> Result<Game<W>, GameError> {
+        let map = self.first_map()?;
+        let (width, height) = match (&map, self.fixed_size()) {
+            (Some(map), _) => (map.width, map.height),
+            (None, Some(size)) => size,
+            (None, None) => {
+                let (w, h) = terminal::size().map_err(|_| GameError::TerminalSizeUnavailable)?;
+                (w as usize, h as usize)
+            }
+        };
+        let snack = self.start_snake(map.as_ref(), (width, height))?;
+        let game = Game::build(writer, width, height, (0, 0), snack)?;
+        self.apply(game, map)
+    }
+    /// 不接终端的游戏,输出全部丢弃,场景已经布置好,直接用`step()`或`simulate()`推进
+    pub fn build_headless(self) -> Result<Game<Sink>, GameError> {
+        let mut game = self.build_with_writer(sink())?;
+        game.build_default()?;
+        Ok(game)
+    }
+    /// 读取存档创建输出到终端的游戏,存档里没有的主题、按键等设置用这个配置的
+    pub fn load<P: AsRef<Path>>(self, path: P) -> Result<Game, GameError> {
+        load_save(self.for_save(), path.as_ref(), GameConfig::build)
+    }
+    /// 读取存档创建输出到任意writer的游戏,方便测试
+    pub fn load_with_writer<P: AsRef<Path>, W: Write>(
+        self,
+        path: P,
+        writer: W,
+    ) -> Result<Game<W>, GameError> {
+        load_save(self.for_save(), path.as_ref(), |config| {
+            config.build_with_writer(writer)
+        })
+    }
+    /// 地图用存档里的,去掉存档不支持的双人和关卡模式
+    fn for_save(mut self) -> Self {
+        self.level = None;
+        self.levels.clear();
+        self.two_player = false;
+        self
+    }
+    /// 设定的棋盘尺寸,每日挑战固定是`DAILY_SIZE`
+    fn fixed_size(&self) -> Option<(usize, usize)> {
+        self.daily.as_ref().map(|_| DAILY_SIZE).or(self.size)
+    }
+    /// 关卡模式的第一关、内置关卡或自定义地图
+    fn first_map(&self) -> Result<Option<Map>, GameError> {
+        let level = self.level.as_deref().map(builtin_level).transpose()?;
+        self.levels
+            .first()
+            .map(String::as_str)
+            .or(level)
+            .or(self.map.as_deref())
+            .map(Map::parse)
+            .transpose()
+    }
+    /// 开局的蛇,`pre_grown_snake`、`initial_snake`、`initial_direction`和地图的起点依次优先,
+    /// 都没有时返回None,用默认的
+    fn start_snake(
+        &self,
+        map: Option<&Map>,
+        (width, height): (usize, usize),
+    ) -> Result<Option<Snack>, GameError> {
+        let size = (width, height);
+        let snack = if let Some(length) = self.pre_grown_snake {
+            Snack::serpentine(length, size)?
+        } else if let Some((head, length, direction)) = self.initial_snake {
+            Snack::straight(head, length.max(1), direction, size)?
+        } else if let Some(direction) = self.initial_direction {
+            Snack::straight((width / 2, height / 2), 8, direction, size)?
+        } else if let Some(map) = map {
+            Snack::straight(map.start, 1, Direction::Right, size)?
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(snack))
+    }
+    /// 把配置写进刚创建的游戏
+    fn apply<W: Write>(self, mut game: Game<W>, map: Option<Map>) -> Result<Game<W>, GameError> {
+        self.key_bindings.validate()?;
+        self.glyphs.validate()?;
+        if !(0.0..=1.0).contains(&self.bonus_chance) {
+            return Err(GameError::InvalidChance(self.bonus_chance));
+        }
+        game.initial_speed = self.speed;
+        game.speed = self.speed;
+        game.border = self.border;
+        game.wrap_cost = self.wrap_cost;
+        game.wall_hug_every = self.wall_hug_every;
+        game.daily = self.daily.clone();
+        if let Some(seed) = self.seed {
+            game.seed = Some(seed);
+            game.rng = ChaCha12Rng::seed_from_u64(seed);
+        }
+        game.initial_lives = self.lives;
+        game.lives = self.lives;
+        game.wall_costs_life = self.wall_costs_life;
+        game.mercy_frames = self.mercy_frames;
+        game.wall_grace = self.wall_grace;
+        game.catch_up_frames = self.catch_up_frames;
+        game.first_food_delay = self.first_food_delay;
+        game.food_decay = self.food_decay;
+        game.food_lifetime = self.food_lifetime;
+        game.food_values = self.food_values;
+        game.food_wall_clearance = self.food_wall_clearance;
+        game.food_head_clearance = self.food_head_clearance;
+        game.target_score = self.target_score;
+        game.target_length = self.target_length;
+        game.bonus_chance = self.bonus_chance;
+        game.runner_every = self.runner_every;
+        game.speed_curve = self.speed_curve;
+        game.time_limit = self.time_limit;
+        game.track_slow_frames = self.track_slow_frames;
+        game.multi_food_bonus = self.multi_food_bonus;
+        game.move_hints = self.move_hints;
+        game.distinct_tail = self.distinct_tail;
+        game.max_input_age_ticks = self.max_input_age_ticks;
+        game.input_priority = self.input_priority;
+        game.blink_warning = self.blink_warning;
+        game.blade_min_length = self.blade_min_length;
+        game.lap_bonus = self.lap_bonus;
+        game.theme = self.theme;
+        game.glyphs = self.glyphs;
+        game.blink_food = self.blink_food;
+        game.background = self.background;
+        game.cell_width = if self.square_cells { 2 } else { 1 };
+        game.countdown = self.countdown;
+        game.hazard_every = self.hazard_every;
+        game.self_collision = self.self_collision;
+        game.tail_chasing = self.tail_chasing;
+        game.invert_controls = self.invert_controls;
+        game.body_gradient = self.body_gradient;
+        game.assist = self.assist;
+        game.ruler = self.ruler;
+        game.no_color = self.no_color;
+        //默认字符里墙和空地一样,只能靠颜色分,换成ASCII字符
+        if self.no_color && self.glyphs == Glyphs::block() {
+            game.glyphs = Glyphs::ascii();
+        }
+        game.edge_warning = self.edge_warning;
+        game.death_animation = !self.reduced_animation;
+        game.wait_for_input = self.wait_for_input;
+        game.opening_input = self.opening_input;
+        game.alternate_screen = self.alternate_screen;
+        game.autosave = self.autosave;
+        if let Some(path) = &self.leaderboard {
+            game.leaderboard = load_leaderboard(path);
+        }
+        game.leaderboard_file = self.leaderboard;
+        if let Some(path) = &self.ghost_run {
+            game.ghost = load_ghost(path, self.seed);
+        }
+        game.ghost_file = self.ghost_run;
+        if let Some(path) = &self.frame_log {
+            //已有的日志覆盖掉
+            game.frame_log = Some(BufWriter::new(File::create(path)?));
+        }
+        game.next_hazard = self.hazard_every.unwrap_or(0);
+        game.shrink_every = self.shrink_every;
+        game.reset_shrink();
+        game.autopilot = self.autopilot;
+        game.food_count = self.food_count;
+        game.initial_food = self.initial_food;
+        game.growth_per_food = self.growth_per_food;
+        game.growth_delay = self.growth_delay;
+        game.speed_keys = self.speed_keys;
+        game.confirm_quit = self.confirm_quit;
+        game.menu = self.menu;
+        game.sound = self.sound;
+        game.ghost_trail = self.ghost_trail;
+        game.key_bindings = self.key_bindings;
+        if let Some(map) = map {
+            game.map_walls = Some(map.walls);
+            game.map_blades = map.blades;
+            game.portals = map.portals;
+        } else if self.arena != Arena::Rectangle || self.border_thickness > 1 {
+            //其他形状的场地按地图的墙处理
+            let (width, height) = game.size();
+            game.map_walls =
+                Some(
+                    self.arena
+                        .walls(width, height, self.border_thickness, self.border),
+                );
+        }
+        let (width, height) = game.size();
+        if let Some(&(x, y)) = self
+            .blades
+            .iter()
+            .find(|&&(x, y)| x >= width || y >= height)
+        {
+            return Err(GameError::CellOutOfBounds(x, y));
+        }
+        game.map_blades.extend(self.blades);
+        //自定义地图和场地的墙可能正好压在默认的开局位置上,建好之前就报错
+        game.check_layout()?;
+        game.levels = parse_levels(&self.levels)?;
+        if self.two_player {
+            let rival = Rival::new(game.size())?;
+            //每一关都要放得下第二条蛇
+            let on_wall = std::iter::once(&rival.snack.head)
+                .chain(rival.snack.bodys.iter())
+                .any(|&pos| {
+                    game.is_layout_wall(pos.0, pos.1)
+                        || game.levels.iter().any(|level| level.walls.contains(&pos))
+                });
+            if on_wall {
+                return Err(GameError::SnakeOnWall);
+            }
+            game.rival = Some(rival);
+            game.bigger_eats_smaller = self.bigger_eats_smaller;
+            game.head_on = self.head_on;
+            game.initial_lives = 1;
+            game.lives = 1;
+            if game.key_bindings == KeyBindings::classic() {
+                game.key_bindings = KeyBindings::two_player();
+            }
+        }
+        //自动存档在存不下来的模式里每次都会失败,建的时候就报错
+        if let (Some(_), Some(mode)) = (&game.autosave, game.unsaveable_mode()) {
+            return Err(GameError::SaveUnsupported(mode));
+        }
+        Ok(game)
+    }
+}
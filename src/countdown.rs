@@ -0,0 +1,189 @@
+use std::{
+    io::Write,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    cursor::MoveTo,
+    event::{poll, read, Event, KeyEventKind},
+    style::{PrintStyledContent, Stylize},
+    QueueableCommand,
+};
+
+use crate::{display_width, paint, Action, Direction, Game, GameError};
+
+/// 倒数结束时显示的字
+const GO: &str = "开始!";
+/// 等待第一次输入时显示的字
+const WAIT_HINT: &str = "按方向键开始";
+
+/// 开局前按的方向键怎么处理
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum OpeningInput {
+    /// 倒数时最后按的方向作为开局方向
+    #[default]
+    LastDirection,
+    /// 开局前按的键都丢掉,包括终端里还没读的,第一帧一定按原来的方向走
+    Ignore,
+}
+impl<W: Write> Game<W> {
+    /// 开局前丢掉所有还没处理的输入,第一帧不会突然转向
+    pub(crate) fn drain_input(&mut self) -> Result<(), GameError> {
+        if self.input_source.is_none() {
+            while poll(Duration::ZERO)? {
+                read()?;
+            }
+        }
+        self.input_queue.clear();
+        if let Some(rival) = self.rival.as_mut() {
+            rival.input_queue.clear();
+        }
+        Ok(())
+    }
+    /// 开局前在棋盘中间倒数,期间按的方向作为开局方向,按退出键返回false
+    pub(crate) fn countdown(&mut self) -> Result<bool, GameError> {
+        let (steps, step) = self.countdown;
+        if steps == 0 {
+            return Ok(true);
+        }
+        let texts = (1..=steps)
+            .rev()
+            .map(|n| n.to_string())
+            .chain(std::iter::once(GO.to_string()));
+        for text in texts {
+            self.print_center(&text)?;
+            let end = Instant::now() + step;
+            while let Some(timeout) = end.checked_duration_since(Instant::now()) {
+                if !self.countdown_input(timeout)? {
+                    return Ok(false);
+                }
+            }
+            self.clear_center(&text)?;
+        }
+        Ok(true)
+    }
+    /// 开局的方向,等待第一次输入时返回None并在棋盘中间提示,有其他输入来源或自动驾驶时不等
+    pub(crate) fn start_direction(&mut self) -> Result<Option<Direction>, GameError> {
+        if !self.wait_for_input || self.autopilot || self.input_source.is_some() {
+            return Ok(Some(self.snack.direction));
+        }
+        //倒数时已经按了方向就直接开始
+        let start = self.take_start_direction()?;
+        if start.is_none() {
+            self.print_center(WAIT_HINT)?;
+        }
+        Ok(start)
+    }
+    /// 取出第一个不掉头的方向,蛇转过去开始走,掉头的方向丢掉
+    pub(crate) fn take_start_direction(&mut self) -> Result<Option<Direction>, GameError> {
+        let moved = self.snack.moved;
+        let Some(dir) = self.next_queued_direction() else {
+            return Ok(None);
+        };
+        if self.controlled(dir) == moved.opposite() {
+            return Ok(None);
+        }
+        self.turn_around(dir);
+        self.clear_center(WAIT_HINT)?;
+        Ok(Some(dir))
+    }
+    /// 倒数时读一个输入,只记下最后一次转向,不会立刻转到墙里
+    fn countdown_input(&mut self, timeout: Duration) -> Result<bool, GameError> {
+        if self.input_source.is_some() {
+            thread::sleep(timeout);
+            return Ok(true);
+        }
+        if !poll(timeout)? {
+            return Ok(true);
+        }
+        if let Event::Key(key_event) = read()? {
+            if key_event.kind == KeyEventKind::Release {
+                return Ok(true);
+            }
+            match self.key_bindings.action(key_event.code) {
+                Some(Action::Quit) => return Ok(false),
+                Some(Action::Turn(_) | Action::Turn2(_))
+                    if self.opening_input == OpeningInput::Ignore => {}
+                Some(Action::Turn(dir)) => {
+                    self.input_queue.clear();
+                    self.queue_direction(dir);
+                }
+                Some(Action::Turn2(dir)) => {
+                    if let Some(rival) = self.rival.as_mut() {
+                        rival.input_queue.clear();
+                    }
+                    self.queue_rival_direction(dir);
+                }
+                _ => {}
+            }
+        }
+        Ok(true)
+    }
+    /// 棋盘中间那一行的起点
+    fn center_of(&self, text: &str) -> (u16, u16) {
+        let (width, height) = self.screen_size();
+        (
+            (width as u16).saturating_sub(display_width(text)) / 2,
+            height as u16 / 2,
+        )
+    }
+    fn print_center(&mut self, text: &str) -> Result<(), GameError> {
+        let (x, y) = self.center_of(text);
+        self.writer
+            .queue(MoveTo(x + self.offset.0, y + self.offset.1))?
+            .queue(PrintStyledContent(paint(
+                self.no_color,
+                text.yellow().bold().on_black(),
+            )))?
+            .flush()?;
+        Ok(())
+    }
+    /// 重画被倒数盖住的格子
+    fn clear_center(&mut self, text: &str) -> Result<(), GameError> {
+        let (x, y) = self.center_of(text);
+        for i in x..x + display_width(text) {
+            self.invalidate_screen(i as usize, y as usize);
+        }
+        self.render_only_updated()?;
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GameConfig, ScriptedInput};
+
+    /// 开局前已经按了向上,缓冲里还没处理
+    fn game_with_queued_turn(opening: OpeningInput) -> Game<Vec<u8>> {
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .seed(1)
+            .opening_input(opening)
+            .build_with_writer(Vec::new())
+            .unwrap();
+        game.build_default().unwrap();
+        //有输入来源时不去读终端
+        game.input_source(ScriptedInput::new(Vec::new()));
+        game.queue_direction(Direction::Up);
+        game
+    }
+
+    #[test]
+    fn ignored_opening_input_never_turns_on_the_first_frame() {
+        let mut game = game_with_queued_turn(OpeningInput::Ignore);
+        let direction = game.snack.direction;
+        game.drain_input().unwrap();
+        assert_eq!(game.next_queued_direction(), None);
+        game.step(None).unwrap();
+        assert_eq!(game.snack.moved, direction);
+    }
+
+    #[test]
+    fn last_opening_direction_is_used_on_the_first_frame() {
+        let mut game = game_with_queued_turn(OpeningInput::LastDirection);
+        let input = game.next_queued_direction();
+        game.step(input).unwrap();
+        assert_eq!(game.snack.moved, Direction::Up);
+    }
+}
@@ -0,0 +1,49 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{leaderboard::civil_from_days, Game, GameConfig, GameError};
+
+/// 每日挑战的棋盘尺寸,固定不变,大家的食物位置才一样;终端放不下时创建失败,大了居中
+pub const DAILY_SIZE: (usize, usize) = (60, 20);
+/// 某天的每日挑战种子,就是`年月日`连起来的数字,如2024-05-01是20240501,
+/// 以后也不会变,同一天的挑战在哪都能复现
+pub fn daily_seed(year: i64, month: u32, day: u32) -> u64 {
+    (year * 10000 + month as i64 * 100 + day as i64) as u64
+}
+/// 今天的日期(年, 月, 日),按UTC算,各地同一刻玩到的是同一个挑战
+pub(crate) fn today() -> (i64, u32, u32) {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    civil_from_days((secs / 86400) as i64)
+}
+impl Game {
+    /// 今天的每日挑战,种子由日期算出,同一天大家的食物和障碍都一样,分数栏显示日期
+    pub fn daily() -> Result<Self, GameError> {
+        GameConfig::new().daily().build()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AutoSize;
+
+    #[test]
+    fn daily_seed_is_the_date_as_a_number() {
+        assert_eq!(daily_seed(2024, 5, 1), 20240501);
+    }
+
+    #[test]
+    fn daily_ignores_other_board_sizes() {
+        for config in [
+            GameConfig::new().size(100, 40).daily(),
+            GameConfig::new().daily().size(100, 40),
+            GameConfig::new().daily().auto_size(AutoSize {
+                aspect: (3, 1),
+                max: None,
+            }),
+        ] {
+            let game = config.build_with_writer(Vec::new()).unwrap();
+            assert_eq!(game.size(), DAILY_SIZE);
+        }
+    }
+}
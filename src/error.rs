@@ -0,0 +1,93 @@
+use std::{error::Error, fmt, io};
+
+/// 游戏错误,正常结束的原因见`GameOutcome`
+#[derive(Debug)]
+pub enum GameError {
+    /// 棋盘尺寸过小,放不下分数栏、开局的蛇和食物
+    BoardTooSmall {
+        width: usize,
+        height: usize,
+        min_width: usize,
+        min_height: usize,
+    },
+    /// 无法获取窗口尺寸
+    TerminalSizeUnavailable,
+    /// 棋盘尺寸超过窗口
+    BoardLargerThanTerminal,
+    /// 宽高比不合法
+    InvalidAspect,
+    /// 没有空间生成食物
+    NoSpaceForFood,
+    /// 地图格式错误
+    InvalidMap(String),
+    /// 蛇超出棋盘
+    SnakeOutOfBounds,
+    /// 蛇压在墙上
+    SnakeOnWall,
+    /// 坐标超出棋盘
+    CellOutOfBounds(usize, usize),
+    /// 没有这个名字的内置关卡
+    UnknownLevel(String),
+    /// 存档格式错误
+    InvalidSave(String),
+    /// 这种模式的局面存不下来
+    SaveUnsupported(&'static str),
+    /// 显示字符不是单列宽
+    InvalidGlyph(char),
+    /// 录像格式错误
+    InvalidReplay(String),
+    /// 概率不在0到1之间
+    InvalidChance(f64),
+    /// 格子上已经有东西
+    CellOccupied(usize, usize),
+    /// 同一个键绑定了不同的操作
+    DuplicateKeyBinding(String),
+    /// 终端读写失败
+    Io(io::Error),
+}
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::BoardTooSmall {
+                width,
+                height,
+                min_width,
+                min_height,
+            } => write!(
+                f,
+                "棋盘尺寸{}x{}过小,至少需要{}x{}",
+                width, height, min_width, min_height
+            ),
+            GameError::TerminalSizeUnavailable => write!(f, "初始化失败:无法获取窗口尺寸"),
+            GameError::BoardLargerThanTerminal => write!(f, "棋盘尺寸超过窗口"),
+            GameError::InvalidAspect => write!(f, "宽高比不能为0"),
+            GameError::NoSpaceForFood => write!(f, "没有足够的空间生成食物"),
+            GameError::InvalidMap(reason) => write!(f, "地图错误: {}", reason),
+            GameError::SnakeOutOfBounds => write!(f, "蛇超出了棋盘"),
+            GameError::SnakeOnWall => write!(f, "蛇的位置上有墙"),
+            GameError::CellOutOfBounds(x, y) => write!(f, "坐标({}, {})超出了棋盘", x, y),
+            GameError::UnknownLevel(name) => write!(f, "没有名为{}的内置关卡", name),
+            GameError::InvalidSave(reason) => write!(f, "存档错误: {}", reason),
+            GameError::SaveUnsupported(mode) => write!(f, "{}不能存档", mode),
+            GameError::InvalidGlyph(c) => write!(f, "字符{:?}不是单列宽", c),
+            GameError::InvalidReplay(reason) => write!(f, "录像错误: {}", reason),
+            GameError::InvalidChance(chance) => write!(f, "概率{}不在0到1之间", chance),
+            GameError::CellOccupied(x, y) => write!(f, "格子({}, {})不是空的", x, y),
+            GameError::DuplicateKeyBinding(key) => write!(f, "按键{}绑定了多个操作", key),
+            GameError::Io(e) => write!(f, "终端读写失败: {}", e),
+        }
+    }
+}
+impl Error for GameError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GameError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl From<io::Error> for GameError {
+    fn from(e: io::Error) -> Self {
+        GameError::Io(e)
+    }
+}
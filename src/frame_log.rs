@@ -0,0 +1,22 @@
+use std::io::Write;
+
+use crate::{CellType, Game, GameError};
+
+impl<W: Write> Game<W> {
+    /// 开启调试日志时记一行这一帧的状态,关掉时什么都不做
+    pub(crate) fn log_frame(
+        &mut self,
+        (next, (x, y)): (CellType, (usize, usize)),
+    ) -> Result<(), GameError> {
+        let Some(log) = self.frame_log.as_mut() else {
+            return Ok(());
+        };
+        let (head_x, head_y) = self.snack.head;
+        writeln!(
+            log,
+            "{} head={},{} dir={:?} next={:?}@{},{} score={} speed={}",
+            self.ticks, head_x, head_y, self.snack.direction, next, x, y, self.score, self.speed
+        )?;
+        Ok(())
+    }
+}
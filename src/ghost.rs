@@ -0,0 +1,78 @@
+use std::{
+    fs,
+    io::{Result as IOResult, Write},
+    path::Path,
+};
+
+use crate::{CellType, Game};
+
+/// 幽灵文件第一行,格式变了就改版本号
+const HEADER: &str = "r_snack ghost 1";
+/// 幽灵显示上一局最近几帧蛇头走过的格子
+const GHOST_LEN: usize = 5;
+
+impl<W: Write> Game<W> {
+    /// 记下这一帧的蛇头,显示上一局同一时刻的幽灵,幽灵只是变暗的空格子,不挡路
+    pub(crate) fn update_ghost(&mut self) {
+        if self.ghost_file.is_none() {
+            return;
+        }
+        self.ghost_path.push(self.snack.head);
+        //上一局已经死了的时刻就不显示了
+        let end = self.ticks as usize;
+        let shown = match self.ghost.get(end.saturating_sub(GHOST_LEN)..end) {
+            Some(cells) => cells.to_vec(),
+            None => Vec::new(),
+        };
+        let (width, height) = self.size();
+        for (x, y) in std::mem::take(&mut self.ghost_shown) {
+            let trail = self.trail.iter().any(|&(pos, _)| pos == (x, y));
+            if x < width
+                && y < height
+                && !shown.contains(&(x, y))
+                && !trail
+                && self.cells[x][y].cell_type == CellType::Empty
+            {
+                self.set_cell_faded(x, y, false);
+            }
+        }
+        for &(x, y) in shown.iter() {
+            if x < width && y < height && self.cells[x][y].cell_type == CellType::Empty {
+                self.set_cell_faded(x, y, true);
+            }
+        }
+        self.ghost_shown = shown;
+    }
+    /// 一局结束后把这一局的路线存成幽灵,下一局跟它比,没有固定种子时食物不一样,不存
+    pub(crate) fn save_ghost(&mut self) -> IOResult<()> {
+        let path = std::mem::take(&mut self.ghost_path);
+        let (Some(file), Some(seed)) = (self.ghost_file.as_ref(), self.seed) else {
+            return Ok(());
+        };
+        let mut text = format!("{}\n{}\n", HEADER, seed);
+        for &(x, y) in path.iter() {
+            text += &format!("{} {}\n", x, y);
+        }
+        fs::write(file, text)?;
+        self.ghost = path;
+        Ok(())
+    }
+}
+/// 读上一局的幽灵,没有文件、格式不对或者种子不同时没有幽灵
+pub(crate) fn load_ghost(path: &Path, seed: Option<u64>) -> Vec<(usize, usize)> {
+    let Ok(text) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let mut lines = text.lines();
+    if lines.next() != Some(HEADER) || lines.next().and_then(|s| s.parse().ok()) != seed {
+        return Vec::new();
+    }
+    //每行是一帧后蛇头的`x y`
+    lines
+        .map(|line| {
+            let (x, y) = line.split_once(' ')?;
+            Some((x.parse().ok()?, y.parse().ok()?))
+        })
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_default()
+}
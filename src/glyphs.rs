@@ -0,0 +1,144 @@
+use crate::{CellType, Direction, GameError};
+
+/// 每种格子显示的字符,每个字符必须只占一列
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Glyphs {
+    pub wall: char,
+    /// 没有朝向时的蛇头,比如残影
+    pub snack_head: char,
+    /// 朝上、下、左、右走时的蛇头
+    pub snack_head_up: char,
+    pub snack_head_down: char,
+    pub snack_head_left: char,
+    pub snack_head_right: char,
+    pub snack_body: char,
+    pub snack2_head: char,
+    pub snack2_body: char,
+    pub snack_tail: char,
+    pub food: char,
+    pub bonus_food: char,
+    pub blade: char,
+    pub poison: char,
+    pub portal: char,
+    pub runner: char,
+    pub empty: char,
+}
+impl Glyphs {
+    /// 默认字符,墙和空地用方块填满
+    pub fn block() -> Self {
+        Glyphs {
+            wall: '█',
+            snack_head: '#',
+            snack_head_up: '^',
+            snack_head_down: 'v',
+            snack_head_left: '<',
+            snack_head_right: '>',
+            snack_body: '#',
+            snack2_head: '#',
+            snack2_body: '#',
+            snack_tail: '~',
+            food: '$',
+            bonus_food: '*',
+            blade: 'X',
+            poison: '%',
+            portal: 'O',
+            runner: '&',
+            empty: '█',
+        }
+    }
+    /// 只用ASCII字符,方块显示不正常的终端也能用,不靠颜色也能分清格子
+    pub fn ascii() -> Self {
+        Glyphs {
+            wall: '+',
+            snack_head: 'O',
+            snack_head_up: '^',
+            snack_head_down: 'v',
+            snack_head_left: '<',
+            snack_head_right: '>',
+            snack_body: 'o',
+            snack2_head: 'Q',
+            snack2_body: 'q',
+            snack_tail: '~',
+            food: '@',
+            bonus_food: '*',
+            blade: 'X',
+            poison: 'x',
+            portal: '0',
+            runner: '&',
+            empty: '.',
+        }
+    }
+    /// 格子类型对应的字符
+    pub fn get(&self, cell_type: CellType) -> char {
+        match cell_type {
+            CellType::Wall => self.wall,
+            CellType::SnackHead => self.snack_head,
+            CellType::SnackBody => self.snack_body,
+            CellType::Snack2Head => self.snack2_head,
+            CellType::Snack2Body => self.snack2_body,
+            CellType::Food => self.food,
+            CellType::BonusFood => self.bonus_food,
+            CellType::Blade => self.blade,
+            CellType::Poison => self.poison,
+            CellType::Portal => self.portal,
+            CellType::Runner => self.runner,
+            CellType::Empty => self.empty,
+        }
+    }
+    /// 朝dir走的蛇头
+    pub fn head(&self, dir: Direction) -> char {
+        match dir {
+            Direction::Up => self.snack_head_up,
+            Direction::Down => self.snack_head_down,
+            Direction::Left => self.snack_head_left,
+            Direction::Right => self.snack_head_right,
+        }
+    }
+    /// 检查每个字符都只占一列,控制字符和中文、emoji这类宽字符会破坏对齐
+    pub fn validate(&self) -> Result<(), GameError> {
+        let all = [
+            self.wall,
+            self.snack_head,
+            self.snack_head_up,
+            self.snack_head_down,
+            self.snack_head_left,
+            self.snack_head_right,
+            self.snack_body,
+            self.snack2_head,
+            self.snack2_body,
+            self.snack_tail,
+            self.food,
+            self.bonus_food,
+            self.blade,
+            self.poison,
+            self.portal,
+            self.runner,
+            self.empty,
+        ];
+        match all.into_iter().find(|&c| !is_single_width(c)) {
+            Some(c) => Err(GameError::InvalidGlyph(c)),
+            None => Ok(()),
+        }
+    }
+}
+impl Default for Glyphs {
+    fn default() -> Self {
+        Glyphs::block()
+    }
+}
+/// 粗略判断字符是否只占一列,排除控制字符和常见的宽字符区间
+fn is_single_width(c: char) -> bool {
+    let wide = matches!(
+        c as u32,
+        0x1100..=0x115F
+            | 0x2E80..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1FAFF
+            | 0x20000..=0x3FFFD
+    );
+    !c.is_control() && !wide
+}
@@ -0,0 +1,59 @@
+use std::io::Write;
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{CellType, Game};
+
+/// 障碍最长几格
+const HAZARD_LEN: usize = 3;
+/// 蛇头周围几格内不放障碍
+const HAZARD_HEAD_CLEARANCE: usize = 2;
+/// 蛇头正前方几格内不放障碍
+const HAZARD_AHEAD: usize = 6;
+
+impl<W: Write> Game<W> {
+    /// 分数每过一个里程碑在空地上加一段障碍墙
+    pub(crate) fn check_hazards(&mut self) {
+        let Some(every) = self.hazard_every else {
+            return;
+        };
+        while self.score >= self.next_hazard {
+            self.spawn_hazard();
+            self.next_hazard += every;
+        }
+    }
+    /// 在远离蛇头和前进路线的空格子上放一段横的或竖的墙,放不下就缩短,一格都放不下就不放。
+    /// 蛇头周围的格子不会变成墙,所以这一帧蛇总有路可走
+    fn spawn_hazard(&mut self) {
+        let (head_x, head_y) = self.snack.head;
+        let allowed = |game: &Self, (x, y): (usize, usize)| {
+            x < game.cells.len()
+                && y < game.cells[0].len()
+                && game.cells[x][y].cell_type == CellType::Empty
+                && (x.abs_diff(head_x) > HAZARD_HEAD_CLEARANCE
+                    || y.abs_diff(head_y) > HAZARD_HEAD_CLEARANCE)
+                && !game.cells_ahead(HAZARD_AHEAD).any(|pos| pos == (x, y))
+        };
+        //候选起点放在`empty_scratch`里,不分配内存
+        let mut starts = std::mem::take(&mut self.empty_scratch);
+        self.collect_empty_cells(&mut starts);
+        starts.retain(|&pos| allowed(self, pos));
+        starts.shuffle(&mut self.rng);
+        let vertical = self.rng.gen_bool(0.5);
+        let cell =
+            |(x, y): (usize, usize), i: usize| if vertical { (x, y + i) } else { (x + i, y) };
+        let segment = (1..=HAZARD_LEN).rev().find_map(|len| {
+            starts
+                .iter()
+                .find(|&&start| (0..len).all(|i| allowed(self, cell(start, i))))
+                .map(|&start| (start, len))
+        });
+        self.empty_scratch = starts;
+        if let Some((start, len)) = segment {
+            for i in 0..len {
+                let (x, y) = cell(start, i);
+                self.set_cell(x, y, CellType::Wall);
+            }
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use std::collections::VecDeque;
+
+use crate::Direction;
+
+/// 游戏循环的输入来源,设置后代替键盘读取每一帧的转向
+pub trait InputSource {
+    /// 这一帧的转向,None表示不转
+    fn next_direction(&mut self) -> Option<Direction>;
+    /// 是否退出游戏
+    fn should_quit(&self) -> bool;
+}
+/// 预先写好的输入,每帧取一个,用完就退出
+pub struct ScriptedInput {
+    inputs: VecDeque<Option<Direction>>,
+}
+impl ScriptedInput {
+    pub fn new<I: IntoIterator<Item = Option<Direction>>>(inputs: I) -> Self {
+        ScriptedInput {
+            inputs: inputs.into_iter().collect(),
+        }
+    }
+}
+impl InputSource for ScriptedInput {
+    fn next_direction(&mut self) -> Option<Direction> {
+        self.inputs.pop_front().flatten()
+    }
+    fn should_quit(&self) -> bool {
+        self.inputs.is_empty()
+    }
+}
@@ -0,0 +1,178 @@
+use crossterm::event::KeyCode;
+
+use crate::{Direction, GameError};
+
+/// 按键对应的操作
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Action {
+    /// 转向
+    Turn(Direction),
+    /// 双人模式玩家二转向
+    Turn2(Direction),
+    /// 退出
+    Quit,
+    /// 暂停或继续
+    Pause,
+    /// 存档后继续
+    Save,
+    /// 加速
+    SpeedUp,
+    /// 减速
+    SpeedDown,
+    /// 暂停时只走一帧
+    Step,
+    /// 显示或隐藏调试信息
+    Debug,
+    /// 一局结束后重新开始
+    Restart,
+    /// 询问时回答是
+    Confirm,
+    /// 询问时回答否
+    Cancel,
+}
+/// 常用的几套操作键,不想一个个绑定时直接选一套,空格暂停、Esc退出等转向以外的键都一样
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum ControlScheme {
+    /// WASD和方向键
+    #[default]
+    Wasd,
+    /// 只用方向键,字母键不转向
+    Arrows,
+    /// vim风格的hjkl和方向键
+    Vim,
+    /// 给左手用鼠标的玩家,右手按ijkl,方向键也能用
+    Ijkl,
+}
+impl ControlScheme {
+    /// 这套操作键对应的绑定
+    pub fn bindings(self) -> KeyBindings {
+        match self {
+            ControlScheme::Wasd => KeyBindings::classic(),
+            ControlScheme::Arrows => KeyBindings::empty().with_common_keys(),
+            ControlScheme::Vim => KeyBindings::vim(),
+            ControlScheme::Ijkl => KeyBindings::empty()
+                .bind_letter('i', Action::Turn(Direction::Up))
+                .bind_letter('j', Action::Turn(Direction::Left))
+                .bind_letter('k', Action::Turn(Direction::Down))
+                .bind_letter('l', Action::Turn(Direction::Right))
+                .with_common_keys(),
+        }
+    }
+}
+/// 按键绑定,一个键只能对应一个操作
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct KeyBindings {
+    bindings: Vec<(KeyCode, Action)>,
+}
+impl KeyBindings {
+    /// 空的绑定
+    pub fn empty() -> Self {
+        KeyBindings {
+            bindings: Vec::new(),
+        }
+    }
+    /// 默认绑定:WASD和方向键转向,空格暂停,Esc退出
+    pub fn classic() -> Self {
+        Self::empty()
+            .bind_letter('w', Action::Turn(Direction::Up))
+            .bind_letter('a', Action::Turn(Direction::Left))
+            .bind_letter('s', Action::Turn(Direction::Down))
+            .bind_letter('d', Action::Turn(Direction::Right))
+            .with_common_keys()
+    }
+    /// vim风格:hjkl和方向键转向,空格暂停,Esc退出
+    pub fn vim() -> Self {
+        Self::empty()
+            .bind_letter('k', Action::Turn(Direction::Up))
+            .bind_letter('h', Action::Turn(Direction::Left))
+            .bind_letter('j', Action::Turn(Direction::Down))
+            .bind_letter('l', Action::Turn(Direction::Right))
+            .with_common_keys()
+    }
+    /// 双人模式:玩家一WASD,玩家二方向键,空格暂停,Esc退出
+    pub fn two_player() -> Self {
+        Self::empty()
+            .bind_letter('w', Action::Turn(Direction::Up))
+            .bind_letter('a', Action::Turn(Direction::Left))
+            .bind_letter('s', Action::Turn(Direction::Down))
+            .bind_letter('d', Action::Turn(Direction::Right))
+            .bind(KeyCode::Up, Action::Turn2(Direction::Up))
+            .bind(KeyCode::Left, Action::Turn2(Direction::Left))
+            .bind(KeyCode::Down, Action::Turn2(Direction::Down))
+            .bind(KeyCode::Right, Action::Turn2(Direction::Right))
+            .with_game_keys()
+    }
+    /// 绑定一个键
+    pub fn bind(mut self, key: KeyCode, action: Action) -> Self {
+        self.bindings.push((key, action));
+        self
+    }
+    /// 绑定一个字母,大小写都算
+    fn bind_letter(self, letter: char, action: Action) -> Self {
+        self.bind(KeyCode::Char(letter.to_ascii_lowercase()), action)
+            .bind(KeyCode::Char(letter.to_ascii_uppercase()), action)
+    }
+    /// 方向键和转向以外的键
+    fn with_common_keys(self) -> Self {
+        self.bind(KeyCode::Up, Action::Turn(Direction::Up))
+            .bind(KeyCode::Left, Action::Turn(Direction::Left))
+            .bind(KeyCode::Down, Action::Turn(Direction::Down))
+            .bind(KeyCode::Right, Action::Turn(Direction::Right))
+            .with_game_keys()
+    }
+    /// 空格暂停,Esc退出,+和=加速,-减速,P存档,暂停时.单步,F3调试信息,结束后R重新开始,
+    /// 询问时Y确认、N取消
+    fn with_game_keys(self) -> Self {
+        self.bind(KeyCode::Char(' '), Action::Pause)
+            .bind(KeyCode::Esc, Action::Quit)
+            .bind(KeyCode::Char('+'), Action::SpeedUp)
+            .bind(KeyCode::Char('='), Action::SpeedUp)
+            .bind(KeyCode::Char('-'), Action::SpeedDown)
+            .bind_letter('p', Action::Save)
+            .bind(KeyCode::Char('.'), Action::Step)
+            .bind(KeyCode::F(3), Action::Debug)
+            .bind_letter('r', Action::Restart)
+            .bind_letter('y', Action::Confirm)
+            .bind_letter('n', Action::Cancel)
+    }
+    /// 按键对应的操作
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|&&(k, _)| k == key)
+            .map(|&(_, action)| action)
+    }
+    /// 绑定到这个操作的第一个键,没绑定时返回None
+    pub fn first_key(&self, action: Action) -> Option<KeyCode> {
+        self.bindings
+            .iter()
+            .find(|&&(_, a)| a == action)
+            .map(|&(key, _)| key)
+    }
+    /// 检查有没有一个键绑定了两个不同的操作
+    pub fn validate(&self) -> Result<(), GameError> {
+        for (i, &(key, action)) in self.bindings.iter().enumerate() {
+            let conflict = self.bindings[i + 1..]
+                .iter()
+                .any(|&(k, a)| k == key && a != action);
+            if conflict {
+                return Err(GameError::DuplicateKeyBinding(format!("{:?}", key)));
+            }
+        }
+        Ok(())
+    }
+}
+/// 按键在提示里的写法,字母用大写
+pub(crate) fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "空格".to_string(),
+        KeyCode::Char(c) => c.to_ascii_uppercase().to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        _ => format!("{:?}", key),
+    }
+}
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
@@ -0,0 +1,179 @@
+use std::{
+    cmp::Reverse,
+    fs,
+    io::{Result as IOResult, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crossterm::{
+    cursor::MoveTo,
+    event::{read, Event, KeyCode, KeyEventKind},
+    style::{PrintStyledContent, Stylize},
+    QueueableCommand,
+};
+
+use crate::{display_width, paint, Game, GameError};
+
+/// 排行榜文件第一行,格式变了就改版本号
+const HEADER: &str = "r_snack leaderboard 1";
+/// 排行榜最多记几条
+const LEADERBOARD_LEN: usize = 10;
+/// 结束面板上显示前几名
+pub(crate) const LEADERBOARD_SHOWN: usize = 5;
+/// 名字最多几个字
+const NAME_LEN: usize = 8;
+/// 没输名字时记的名字
+const NO_NAME: &str = "无名";
+
+/// 排行榜上的一条记录
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ScoreEntry {
+    pub score: usize,
+    pub name: String,
+    /// 记录的日期,从1970-01-01起的秒数
+    pub date: u64,
+}
+impl ScoreEntry {
+    /// 记录日期,按UTC算,如`2024-05-01`
+    pub fn date_string(&self) -> String {
+        let (year, month, day) = civil_from_days((self.date / 86400) as i64);
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+impl<W: Write> Game<W> {
+    /// 排行榜,分数从高到低,没有设置排行榜文件时是空的
+    pub fn leaderboard(&self) -> &[ScoreEntry] {
+        &self.leaderboard
+    }
+    /// 一局结束后分数能上榜就让玩家输入名字,记进排行榜并写回文件,
+    /// 没有设置排行榜文件、不是键盘操作或上不了榜时什么都不做
+    pub(crate) fn record_score(&mut self, score: usize) -> Result<(), GameError> {
+        let Some(path) = self.leaderboard_file.clone() else {
+            return Ok(());
+        };
+        if self.input_source.is_some() || self.autopilot || !self.qualifies(score) {
+            return Ok(());
+        }
+        let name = self.prompt_name()?;
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        //同分的排在先上榜的后面
+        let i = self
+            .leaderboard
+            .partition_point(|entry| entry.score >= score);
+        self.leaderboard.insert(i, ScoreEntry { score, name, date });
+        self.leaderboard.truncate(LEADERBOARD_LEN);
+        save_leaderboard(&path, &self.leaderboard)?;
+        Ok(())
+    }
+    /// 分数能不能上榜
+    fn qualifies(&self, score: usize) -> bool {
+        score > 0
+            && (self.leaderboard.len() < LEADERBOARD_LEN
+                || self
+                    .leaderboard
+                    .last()
+                    .is_some_and(|last| score > last.score))
+    }
+    /// 在棋盘中间让玩家输入名字,回车确定,Esc或不输入记成无名
+    fn prompt_name(&mut self) -> IOResult<String> {
+        let mut name = String::new();
+        loop {
+            self.print_name_prompt(&name)?;
+            let Event::Key(key_event) = read()? else {
+                continue;
+            };
+            if key_event.kind == KeyEventKind::Release {
+                continue;
+            }
+            match key_event.code {
+                KeyCode::Enter => break,
+                KeyCode::Esc => {
+                    name.clear();
+                    break;
+                }
+                KeyCode::Backspace => {
+                    name.pop();
+                }
+                KeyCode::Char(c) if !c.is_control() && name.chars().count() < NAME_LEN => {
+                    name.push(c)
+                }
+                _ => {}
+            }
+        }
+        let name = name.trim();
+        Ok(if name.is_empty() {
+            NO_NAME.to_string()
+        } else {
+            name.to_string()
+        })
+    }
+    fn print_name_prompt(&mut self, name: &str) -> IOResult<()> {
+        //按名字最长时的宽度画,删字的时候旧的字会被盖掉
+        let width = display_width("新纪录! 名字: ") + NAME_LEN as u16 * 2 + 1;
+        let text = format!("新纪录! 名字: {}_", name);
+        let text = format!(
+            "{}{}",
+            text,
+            " ".repeat((width - display_width(&text)) as usize)
+        );
+        let (screen_w, screen_h) = self.screen_size();
+        let x = (screen_w as u16).saturating_sub(width) / 2;
+        let y = screen_h as u16 / 2;
+        self.writer
+            .queue(MoveTo(x + self.offset.0, y + self.offset.1))?
+            .queue(PrintStyledContent(paint(
+                self.no_color,
+                text.yellow().bold().on_dark_grey(),
+            )))?
+            .flush()?;
+        Ok(())
+    }
+}
+/// 读排行榜文件,没有文件或格式不对时当作空的,不报错
+pub(crate) fn load_leaderboard(path: &Path) -> Vec<ScoreEntry> {
+    let Ok(text) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let mut lines = text.lines();
+    if lines.next() != Some(HEADER) {
+        return Vec::new();
+    }
+    //每行是`分数\t日期\t名字`
+    let entries = lines
+        .map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            Some(ScoreEntry {
+                score: fields.next()?.parse().ok()?,
+                date: fields.next()?.parse().ok()?,
+                name: fields.next()?.to_string(),
+            })
+        })
+        .collect::<Option<Vec<_>>>();
+    let mut entries = entries.unwrap_or_default();
+    entries.sort_by_key(|entry| Reverse(entry.score));
+    entries.truncate(LEADERBOARD_LEN);
+    entries
+}
+fn save_leaderboard(path: &Path, entries: &[ScoreEntry]) -> IOResult<()> {
+    let mut text = format!("{}\n", HEADER);
+    for entry in entries {
+        text += &format!("{}\t{}\t{}\n", entry.score, entry.date, entry.name);
+    }
+    fs::write(path, text)
+}
+/// 从1970-01-01起的天数换算成(年, 月, 日)
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,136 @@
+use crate::GameError;
+
+/// 内置的命名关卡,名字和文本地图,`#`是墙,`.`是空地,`S`是蛇头起点,`X`是刀刃
+const LEVELS: [(&str, &str); 4] = [
+    //只有边界墙的空场地
+    ("classic", CLASSIC),
+    //中间一大块墙,绕着圈走
+    ("donut", DONUT),
+    //中间一个十字,四个角落连通
+    ("cross", CROSS),
+    //一条螺旋墙,越往里越窄
+    ("spiral", SPIRAL),
+];
+const CLASSIC: &str = "
+############################################################
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#........S.................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+############################################################
+";
+const DONUT: &str = "
+############################################################
+#..........................................................#
+#..........................................................#
+#........S.................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#...................####################...................#
+#...................####################...................#
+#...................####################...................#
+#...................####################...................#
+#...................####################...................#
+#...................####################...................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+############################################################
+";
+const CROSS: &str = "
+############################################################
+#..........................................................#
+#..........................................................#
+#........S.................................................#
+#..........................................................#
+#.............................#............................#
+#.............................#............................#
+#.............................#............................#
+#.............................#............................#
+#.............................#............................#
+#..............##############################..............#
+#.............................#............................#
+#.............................#............................#
+#.............................#............................#
+#.............................#............................#
+#.............................#............................#
+#..........................................................#
+#..........................................................#
+#..........................................................#
+############################################################
+";
+const SPIRAL: &str = "
+############################################################
+#.S........................................................#
+#..........................................................#
+#...####################################################...#
+#......................................................#...#
+#......................................................#...#
+#.......############################################...#...#
+#.......#..........................................#...#...#
+#.......#..........................................#...#...#
+#.......#...##################################.....#...#...#
+#.......#...#......................................#...#...#
+#.......#...#......................................#...#...#
+#.......#...#......................................#...#...#
+#.......#...########################################...#...#
+#.......#..............................................#...#
+#.......#..............................................#...#
+#.......################################################...#
+#..........................................................#
+#..........................................................#
+############################################################
+";
+
+/// 按名字取内置关卡的文本地图
+pub(crate) fn builtin_level(name: &str) -> Result<&'static str, GameError> {
+    LEVELS
+        .iter()
+        .find(|(level, _)| *level == name)
+        .map(|(_, map)| *map)
+        .ok_or_else(|| GameError::UnknownLevel(name.to_string()))
+}
+/// 所有内置关卡的名字
+pub fn level_names() -> impl Iterator<Item = &'static str> {
+    LEVELS.iter().map(|(name, _)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Map;
+
+    #[test]
+    fn every_builtin_level_parses() {
+        for name in level_names() {
+            let map = Map::parse(builtin_level(name).unwrap());
+            assert!(map.is_ok(), "{}: {:?}", name, map.err());
+        }
+    }
+
+    #[test]
+    fn unknown_level_is_an_error() {
+        assert!(matches!(
+            builtin_level("maze"),
+            Err(GameError::UnknownLevel(name)) if name == "maze"
+        ));
+    }
+}
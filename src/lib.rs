@@ -1,26 +1,149 @@
 use std::{
-    collections::LinkedList,
-    fmt::Debug,
-    io::{stdout, Error, ErrorKind, Result as IOResult, Write},
+    collections::{HashMap, VecDeque},
+    fmt::{Debug, Display},
+    fs::File,
+    io::{stdout, BufWriter, Result as IOResult, Stdout, Write},
+    ops::Range,
+    panic,
+    path::PathBuf,
+    sync::Arc,
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crossterm::{
-    cursor::{Hide, MoveRight, MoveTo},
-    event::{poll, read, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
-    style::{PrintStyledContent, StyledContent, Stylize},
-    terminal::{
-        self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
-        SetTitle,
-    },
+    cursor::{MoveTo, Show},
+    event::{poll, read, DisableFocusChange, Event, KeyCode, KeyEvent, KeyEventKind},
+    style::{ContentStyle, Print, PrintStyledContent, StyledContent, Stylize},
+    terminal::{disable_raw_mode, Clear, ClearType, LeaveAlternateScreen},
     ExecutableCommand, QueueableCommand,
 };
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
 
-pub struct Game {
+mod arena;
+mod background;
+mod border;
+mod campaign;
+mod config;
+mod countdown;
+mod daily;
+mod error;
+mod frame_log;
+mod ghost;
+mod glyphs;
+mod hazard;
+mod input;
+mod keys;
+mod leaderboard;
+mod levels;
+mod map;
+mod minimap;
+mod outcome;
+mod panel;
+mod renderer;
+mod replay;
+mod resize;
+mod rival;
+mod runner;
+mod save;
+mod script;
+mod shrink;
+mod signals;
+mod spawner;
+mod terminal;
+mod theme;
+pub use arena::Arena;
+pub use background::BackgroundStyle;
+pub use border::{BorderMode, Edge};
+pub use config::GameConfig;
+pub use countdown::OpeningInput;
+pub use daily::{daily_seed, DAILY_SIZE};
+pub use error::GameError;
+pub use glyphs::Glyphs;
+pub use input::{InputSource, ScriptedInput};
+use keys::key_label;
+pub use keys::{Action, ControlScheme, KeyBindings};
+pub use leaderboard::ScoreEntry;
+use leaderboard::LEADERBOARD_SHOWN;
+pub use levels::level_names;
+pub use outcome::GameOutcome;
+pub use renderer::Renderer;
+use resize::new_cells;
+use rival::Rival;
+pub use rival::{BodyHit, HeadOn, Winner};
+pub use script::GameStep;
+use signals::SignalGuard;
+pub use spawner::{FirstEmpty, FoodSpawner, ScriptedFood};
+use terminal::Terminal;
+pub use theme::Theme;
+
+/// 默认开局的棋盘最小宽度,分数栏要放得下
+const MIN_WIDTH: usize = 60;
+/// 默认开局的棋盘最小高度
+const MIN_HEIGHT: usize = 20;
+/// 开局速度,每帧间隔毫秒数
+const INITIAL_SPEED: u64 = 80;
+/// 每吃一个食物帧间隔缩短的毫秒数
+const SPEED_STEP: u64 = 3;
+/// 帧间隔最短毫秒数
+const MIN_SPEED: u64 = 30;
+/// 被刀刃砍断后蛇至少要剩几节(包括蛇头),不够就结束
+const BLADE_MIN_LENGTH: usize = 3;
+/// 绕圈奖励的提示在分数栏显示几帧
+const LAP_MESSAGE_FRAMES: u64 = 20;
+/// 最多缓存多少个还没处理的转向
+const INPUT_QUEUE_LEN: usize = 3;
+/// 手动调速每次改变的毫秒数
+const SPEED_KEY_STEP: u64 = 10;
+/// 帧间隔最长毫秒数
+const MAX_SPEED: u64 = 300;
+/// 吃完普通食物后出现奖励食物的概率
+const BONUS_CHANCE: f64 = 0.2;
+/// 穿墙扣分时那一格闪几帧
+const WRAP_FLASH_FRAMES: u64 = 3;
+/// 改动的格子占范围的比例(分子, 分母)不低于这个值时整块重画,否则逐格移动光标
+const REGION_DENSITY: (usize, usize) = (1, 2);
+/// 蛇身由亮变暗分几档
+const BODY_SHADES: usize = 3;
+/// 奖励食物存在的帧数
+const BONUS_FRAMES: u64 = 50;
+/// 奖励食物的分值
+const BONUS_SCORE: usize = 5;
+/// 吃完普通食物后出现毒药的概率
+const POISON_CHANCE: f64 = 0.1;
+/// 吃到毒药缩短的节数
+const POISON_SEGMENTS: usize = 3;
+/// 吃到毒药扣的分
+const POISON_PENALTY: usize = 3;
+/// 开始菜单里的模式
+const MENU_MODES: [&str; 3] = ["经典", "穿墙", "限时"];
+/// 开始菜单里的难度和对应的开局速度
+const MENU_LEVELS: [(&str, u64); 3] = [("简单", 120), ("普通", INITIAL_SPEED), ("困难", 50)];
+/// 开始菜单选择限时模式时的时长
+const MENU_TIME_LIMIT: Duration = Duration::from_secs(60);
+/// 按P存档的文件
+const SAVE_FILE: &str = "snack.save";
+/// 蛇尾残影保留的帧数
+const TRAIL_FRAMES: u64 = 3;
+/// 复活后闪烁的帧数
+const RESPAWN_FLASH_FRAMES: u64 = 10;
+/// 上次吃到食物后多少帧内再吃算连击
+const COMBO_WINDOW: u64 = 30;
+/// 连击倍数上限
+const MAX_COMBO: usize = 3;
+/// 宽限模式第一次撞墙时额外停顿的毫秒数
+const WALL_GRACE_PAUSE: u64 = 300;
+/// 开局倒数每一下的时间
+const COUNTDOWN_STEP: Duration = Duration::from_millis(500);
+/// 死亡动画每节最长的时间
+const DEATH_FRAME: Duration = Duration::from_millis(40);
+/// 死亡动画最长的总时间
+const DEATH_ANIMATION: Duration = Duration::from_secs(1);
+
+pub struct Game<W: Write = Stdout> {
     /// 屏幕
-    writer: Box<dyn Write>,
+    writer: W,
     /// 所有格子,二维方格
     cells: Vec<Vec<Cell>>,
     /// 蛇
@@ -28,11 +151,421 @@ pub struct Game {
     /// 分数
     score: usize,
     speed: u64,
+    /// 开局速度
+    initial_speed: u64,
+    /// 已经走过的帧数
+    ticks: u64,
+    /// 开局多少帧后才出现第一个食物,0表示立即出现
+    first_food_delay: u64,
+    /// 每个食物生成时离蛇头的距离,食物没了就删掉
+    food_distances: HashMap<(usize, usize), usize>,
+    /// 按食物生成时离蛇头的远近给的额外奖励,None时不奖励
+    multi_food_bonus: Option<MultiFoodBonus>,
+    /// 棋盘在终端中的偏移,用于居中
+    offset: (u16, u16),
+    /// 教学模式,在自动驾驶建议走的下一格上画淡色提示
+    move_hints: bool,
+    /// 现在提示的格子
+    move_hint: Option<(usize, usize)>,
+    /// 蛇尾用单独的字符和颜色显示
+    distinct_tail: bool,
+    /// 现在标记成蛇尾的格子
+    tail_cell: Option<(usize, usize)>,
+    /// 食物只在离消失不到这么多帧时闪烁,None时一直闪烁
+    blink_warning: Option<u64>,
+    /// 开局时的蛇,重新开始时恢复
+    initial_snack: Snack,
+    /// 自定义地图的墙,None时使用边界墙
+    map_walls: Option<Vec<(usize, usize)>>,
+    /// 穿墙时横穿或纵穿一整圈的奖励分,0时不奖励
+    lap_bonus: usize,
+    /// 上一次穿墙时的方向,同一方向再穿一次就是绕了一圈
+    last_wrap: Option<Direction>,
+    /// 绕圈提示还要显示的帧数
+    lap_message: u64,
+    /// 刀刃的位置
+    map_blades: Vec<(usize, usize)>,
+    /// 成对的传送门
+    portals: Vec<((usize, usize), (usize, usize))>,
+    /// 录像时每一帧的输入
+    recording: Option<Vec<Option<Direction>>>,
+    /// 第一次撞墙时停一帧给玩家转向
+    wall_grace: bool,
+    /// 上一帧差点撞上的墙,正在闪烁
+    grace_wall: Option<(usize, usize)>,
+    /// 蛇头贴着墙走多少帧加1分,None时不加
+    wall_hug_every: Option<u64>,
+    /// 贴墙走了还没换成分数的帧数
+    wall_hug_frames: u64,
+    /// 这一局贴墙一共加的分
+    wall_hug_bonus: usize,
+    /// 穿墙扣分时的分数,0时穿墙不扣分
+    wrap_cost: usize,
+    /// 正在闪的穿墙位置和还剩的帧数
+    wrap_flash: Option<((usize, usize), u64)>,
+    /// 关卡模式的所有地图,为空时不是关卡模式
+    levels: Vec<map::Map>,
+    /// 当前关卡,从0开始
+    level: usize,
+    /// 竞速模式的目标分数
+    target_score: Option<usize>,
+    /// 速度随分数变化的曲线,None时每吃一个食物快一点
+    speed_curve: Option<SpeedCurve>,
+    /// 会跑的食物的位置
+    runner: Option<(usize, usize)>,
+    /// 会跑的食物每隔几帧挪一格,None时没有会跑的食物
+    runner_every: Option<u64>,
+    /// 吃到食物后出现奖励食物的概率
+    bonus_chance: f64,
+    /// 蛇长到这么长就赢,None时不限
+    target_length: Option<usize>,
+    /// 竞速模式开始计时的时间
+    run_start: Option<Instant>,
+    /// 代替键盘的输入来源
+    input_source: Option<Box<dyn InputSource>>,
+    /// 决定食物位置,None时随机放
+    food_spawner: Option<Box<dyn FoodSpawner>>,
+    /// 其他输出后端,None时用crossterm画到终端
+    renderer: Option<Box<dyn Renderer>>,
+    /// 卡顿时最多补几帧逻辑
+    catch_up_frames: usize,
+    /// 上次吃到食物的帧数
+    last_eat_tick: Option<u64>,
+    /// 当前连击倍数,没有连击时是1
+    combo: usize,
+    /// F3切换的调试信息,显示帧率、渲染耗时和更新的格子数
+    debug_overlay: bool,
+    /// 被刀刃砍断后至少要剩的长度,包括蛇头
+    blade_min_length: usize,
+    /// 输入缓冲,每帧处理一个转向,记着按键时是第几帧
+    input_queue: VecDeque<(Direction, u64)>,
+    /// 转向超过这么多帧还没处理就丢掉,None时不限制
+    max_input_age_ticks: Option<u64>,
+    /// 同一帧里冲突的转向键怎么处理
+    input_priority: InputPriority,
+    /// 这一帧还没处理的转向是哪个键按的
+    input_key: Option<KeyCode>,
+    /// 会话统计,跨局累计
+    session: GameStats,
+    /// 本局统计
+    stats: Stats,
+    /// 是否统计超时的帧
+    track_slow_frames: bool,
+    /// 逻辑加渲染耗时超过一帧时长的帧数
+    slow_frames: usize,
+    /// 场上的食物,位置和生成时的帧数
+    foods: Vec<((usize, usize), u64)>,
+    /// 食物放多少帧没吃就烂掉,和烂掉时扣的分
+    food_lifetime: Option<(u64, usize)>,
+    /// 开启随机分值时每个食物的分值,食物没了就删掉
+    food_points: HashMap<(usize, usize), usize>,
+    /// 食物分值的随机范围,None时每个食物1分
+    food_values: Option<(usize, usize)>,
+    /// 同时存在的食物数量
+    food_count: usize,
+    /// 开局放的食物数量,比同时存在的数量多时吃掉的不补,吃到剩`food_count`个再补
+    initial_food: usize,
+    /// 吃一个食物长几节
+    growth_per_food: usize,
+    /// 还没长出来的节数,每帧不缩尾巴长一节
+    pending_growth: usize,
+    /// 吃到食物后过几帧才开始变长,0时吃的这一帧就长
+    growth_delay: u64,
+    /// 推迟的生长,到第几帧时加进`pending_growth`和加几节
+    delayed_growth: VecDeque<(u64, usize)>,
+    /// 能否用+/-手动调速
+    speed_keys: bool,
+    /// 开局前显示开始菜单
+    menu: bool,
+    /// 吃食物和死亡时响铃
+    sound: bool,
+    /// 蛇尾离开的格子短暂留下残影
+    ghost_trail: bool,
+    /// 存上一局路线的幽灵文件,None时不显示幽灵
+    ghost_file: Option<PathBuf>,
+    /// 上一局每一帧后蛇头的位置
+    ghost: Vec<(usize, usize)>,
+    /// 这一局每一帧后蛇头的位置,结束后存成幽灵
+    ghost_path: Vec<(usize, usize)>,
+    /// 正显示成幽灵的格子
+    ghost_shown: Vec<(usize, usize)>,
+    /// 有残影的格子和剩余帧数
+    trail: Vec<((usize, usize), u64)>,
+    /// 按键绑定
+    key_bindings: KeyBindings,
+    /// 双人模式的第二条蛇
+    rival: Option<Rival>,
+    /// 双人模式蛇头撞到对方蛇身时长的一方活下来
+    bigger_eats_smaller: bool,
+    /// 双人模式两个蛇头撞进同一格时的规则
+    head_on: HeadOn,
+    /// 游戏事件回调
+    on_event: Option<Box<dyn FnMut(GameEvent)>>,
+    /// 食物分值衰减,None表示固定1分
+    food_decay: Option<FoodDecay>,
+    /// 食物与墙之间至少隔开的格数
+    food_wall_clearance: usize,
+    /// 蛇头正前方几格内不放食物
+    food_head_clearance: usize,
+    /// 暂停中
+    paused: bool,
+    /// 失去焦点时自动暂停的,回来时自动继续
+    focus_paused: bool,
+    /// 按退出键先询问,按y才退出
+    confirm_quit: bool,
+    /// 正在询问是否退出,记着询问前是否已经暂停
+    quit_prompt: Option<bool>,
+    /// 每条边是墙还是能穿过,从一边出去从对边进来
+    border: BorderMode,
+    /// 随机数种子,None时随机
+    seed: Option<u64>,
+    /// 每日挑战的日期,分数栏上显示
+    daily: Option<String>,
+    /// 随机数生成器,和`rand`的`StdRng`相同,但能取出状态存档
+    rng: ChaCha12Rng,
+    /// 奖励食物的位置和剩余帧数
+    bonus: Option<((usize, usize), u64)>,
+    /// 毒药的位置
+    poison: Option<(usize, usize)>,
+    /// 剩余的命
+    lives: usize,
+    /// 每局开始时的命
+    initial_lives: usize,
+    /// 撞墙是否也只扣一条命
+    wall_costs_life: bool,
+    /// 复活后还要闪烁的帧数
+    respawn_flash: u64,
+    /// 复活后无敌几帧,0时不无敌
+    mercy_frames: u64,
+    /// 还剩几帧无敌,撞墙和撞自己都不死
+    invuln_frames: u64,
+    /// 无敌时穿过自己后蛇身可能还叠着的帧数,蛇尾走过叠着的格子之前清蛇尾要看下面还有没有蛇身
+    self_overlap: u64,
+    /// 限时模式的时长,None为无尽模式
+    time_limit: Option<Duration>,
+    /// 配色
+    theme: Theme,
+    /// 格子显示的字符
+    glyphs: Glyphs,
+    /// 食物闪烁
+    blink_food: bool,
+    /// 空格子的背景图案
+    background: BackgroundStyle,
+    /// 分数栏画在棋盘下面一行
+    hud_below: bool,
+    /// 分数栏上次画的每一项:从第几列开始和内容
+    hud: Vec<(u16, String)>,
+    /// 一格占几列,终端字符高是宽的两倍,占两列看起来才是方的
+    cell_width: u16,
+    /// 调试日志,每帧一行
+    frame_log: Option<BufWriter<File>>,
+    /// 自动存档的文件和间隔
+    autosave: Option<(PathBuf, Duration)>,
+    /// 排行榜文件,None时不记排行榜
+    leaderboard_file: Option<PathBuf>,
+    /// 排行榜,分数从高到低
+    leaderboard: Vec<ScoreEntry>,
+    /// 死的时候播放动画再显示结束面板
+    death_animation: bool,
+    /// 倒数后蛇不动,等玩家按了方向键才朝那边走
+    wait_for_input: bool,
+    /// 开局前按的方向键怎么处理
+    opening_input: OpeningInput,
+    /// 蛇头快撞墙时高亮那面墙
+    edge_warning: bool,
+    /// 现在高亮的墙
+    warned_wall: Option<(usize, usize)>,
+    /// 撞到自己会死,关掉时蛇从自己身上穿过去
+    self_collision: bool,
+    /// 蛇头能走进这一帧正好空出来的蛇尾
+    tail_chasing: bool,
+    /// 反向操作,按左往右转,按上往下转
+    invert_controls: bool,
+    /// 蛇身从蛇头到蛇尾由亮变暗
+    body_gradient: bool,
+    /// 辅助模式,没按方向键时自动朝食物拐
+    assist: bool,
+    /// 在边上的墙上显示行号和列号
+    ruler: bool,
+    /// 不输出颜色和样式,只输出字符
+    no_color: bool,
+    /// 分数每涨多少加一段障碍墙,None时不加
+    hazard_every: Option<usize>,
+    /// 下一段障碍墙在多少分时出现
+    next_hazard: usize,
+    /// 缩圈模式每隔多久最外一圈变成墙,None时不缩
+    shrink_every: Option<Duration>,
+    /// 游戏时间到多少时下一次缩圈
+    next_shrink: Duration,
+    /// 下一次从离边上几格的那一圈开始找
+    shrink_depth: usize,
+    /// 缩圈时被蛇占着、等蛇走开再变成墙的格子
+    shrink_pending: Vec<(usize, usize)>,
+    /// 开局倒数几下,每下多久,0下不倒数
+    countdown: (usize, Duration),
+    /// 棋盘比终端大时缩小显示,一个字符代表(宽, 高)一块格子
+    zoom: Option<(usize, usize)>,
+    /// 大分数面板左上角在终端上的位置,None时不画面板
+    panel: Option<(u16, u16)>,
+    /// 是否还在备用屏幕中,不用备用屏幕时是否还在画面里
+    in_alt_screen: bool,
+    /// 是否切到备用屏幕,不切时直接画在当前屏幕上,退出后画面留在滚动记录里
+    alternate_screen: bool,
+    /// 自动驾驶,不读方向键
+    autopilot: bool,
+    /// 从存档恢复,开局不重新布置场景
+    loaded: bool,
+    /// 待渲染的格子
+    dirty: Vec<(usize, usize)>,
+    ///放食物时找空格子用的缓冲,每次清空重用,不用每次都重新分配
+    empty_scratch: Vec<(usize, usize)>,
+}
+/// 食物放得越久分值越低
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct FoodDecay {
+    /// 刚生成时的分值
+    pub max: usize,
+    /// 最低分值
+    pub floor: usize,
+    /// 每隔多少帧减1分
+    pub ticks_per_point: u64,
+}
+impl FoodDecay {
+    /// 放置了elapsed帧后的分值
+    fn value(&self, elapsed: u64) -> usize {
+        let lost = (elapsed / self.ticks_per_point.max(1)) as usize;
+        self.max.saturating_sub(lost).max(self.floor)
+    }
+}
+/// 速度随分数变化的曲线,刚开始慢,分数越高越快,不会快过floor
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum SpeedCurve {
+    /// 每得1分帧间隔减少step毫秒
+    Linear { step: f64, floor: u64 },
+    /// 每得1分帧间隔乘以ratio,ratio小于1
+    Exponential { ratio: f64, floor: u64 },
+}
+impl SpeedCurve {
+    /// 平缓的线性曲线
+    pub fn gentle() -> Self {
+        SpeedCurve::Linear {
+            step: 1.0,
+            floor: MIN_SPEED,
+        }
+    }
+    /// 越到后面加速越少的指数曲线,前期加速很快
+    pub fn steep() -> Self {
+        SpeedCurve::Exponential {
+            ratio: 0.97,
+            floor: MIN_SPEED,
+        }
+    }
+    /// 开局速度为initial时,score分对应的帧间隔
+    fn speed(&self, initial: u64, score: usize) -> u64 {
+        let (speed, floor) = match *self {
+            SpeedCurve::Linear { step, floor } => (initial as f64 - step * score as f64, floor),
+            SpeedCurve::Exponential { ratio, floor } => {
+                (initial as f64 * ratio.powi(score as i32), floor)
+            }
+        };
+        //曲线不会比开局更慢
+        (speed.max(0.0) as u64).clamp(floor.min(initial), initial)
+    }
+}
+/// 会话统计,不随单局重置
+#[derive(PartialEq, Eq, Clone, Default, Debug)]
+pub struct GameStats {
+    /// 已结束的局数
+    pub games_played: usize,
+    /// 累计吃到的食物
+    pub food_eaten: usize,
+    /// 本次会话最高分
+    pub best_score: usize,
 }
+/// 同一帧里按了几个转向键时怎么处理,一帧指两次移动之间
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum InputPriority {
+    /// 都按顺序放进输入缓冲,之后每帧处理一个
+    #[default]
+    Buffered,
+    /// 只算这一帧最后按的
+    LastWins,
+    /// 只算这一帧最先按的,之后按的都不算
+    FirstWins,
+    /// 字母键优先,这一帧按过字母键后方向键不算,字母键之间最后按的算
+    LettersFirst,
+    /// 方向键优先,这一帧按过方向键后字母键不算,方向键之间最后按的算
+    ArrowsFirst,
+}
+impl InputPriority {
+    /// 同一帧里先按了earlier再按later时是不是保留先按的
+    fn keeps_earlier(self, earlier: KeyCode, later: KeyCode) -> bool {
+        let arrow = |key| {
+            matches!(
+                key,
+                KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right
+            )
+        };
+        match self {
+            InputPriority::Buffered | InputPriority::LastWins => false,
+            InputPriority::FirstWins => true,
+            InputPriority::LettersFirst => !arrow(earlier) && arrow(later),
+            InputPriority::ArrowsFirst => arrow(earlier) && !arrow(later),
+        }
+    }
+}
+/// 多食物模式下按食物生成时离蛇头的距离给的额外奖励
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum MultiFoodBonus {
+    /// 吃到的是生成时离蛇头最近的食物时多加这么多分,鼓励规划路线
+    Closest(usize),
+    /// 吃到的是生成时离蛇头最远的食物时多加这么多分
+    Farthest(usize),
+}
+/// 单局统计,重新开始时清零
+#[derive(PartialEq, Eq, Clone, Default, Debug)]
+pub struct Stats {
+    /// 吃到的食物
+    pub food_eaten: usize,
+    /// 转向次数
+    pub turns: usize,
+    /// 坚持的帧数
+    pub frames: u64,
+    /// 实际游玩的时间,不含暂停
+    pub play_time: Duration,
+}
+/// 按宽高比自动选择棋盘尺寸
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct AutoSize {
+    /// 宽高比,如(4, 3)
+    pub aspect: (u16, u16),
+    /// 最大尺寸(宽, 高)
+    pub max: Option<(usize, usize)>,
+}
+impl AutoSize {
+    /// 在可用空间内符合宽高比的最大尺寸
+    fn fit(&self, avail_w: usize, avail_h: usize) -> Result<(usize, usize), GameError> {
+        let (aspect_w, aspect_h) = self.aspect;
+        if aspect_w == 0 || aspect_h == 0 {
+            return Err(GameError::InvalidAspect);
+        }
+        let (mut avail_w, mut avail_h) = (avail_w, avail_h);
+        if let Some((max_w, max_h)) = self.max {
+            avail_w = avail_w.min(max_w);
+            avail_h = avail_h.min(max_h);
+        }
+        let width = avail_w.min(avail_h * aspect_w as usize / aspect_h as usize);
+        let height = width * aspect_h as usize / aspect_w as usize;
+        Ok((width, height))
+    }
+}
+#[derive(Clone)]
 struct Snack {
     direction: Direction,
+    /// 上一帧实际移动的方向
+    moved: Direction,
     head: (usize, usize),
-    bodys: LinkedList<(usize, usize)>,
+    bodys: VecDeque<(usize, usize)>,
 }
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct Cell {
@@ -40,111 +573,612 @@ pub struct Cell {
     x: usize,
     /// 行
     y: usize,
+    /// 已经在待渲染列表里
     changed_flag: bool,
     cell_type: CellType,
+    /// 变暗显示
+    faded: bool,
+    /// 高亮显示,撞墙提醒用
+    highlighted: bool,
+    /// 蛇身按离蛇头的远近分的深浅档,0最亮
+    shade: u8,
+    /// 是蛇尾,单独显示蛇尾时用蛇尾的字符和颜色
+    tail: bool,
+    /// 食物快过期了,只在快过期时闪烁的模式下闪烁
+    expiring: bool,
+    /// 上一次实际画到屏幕上的样子(类型, 变暗, 高亮, 蛇尾, 快过期, 深浅, 特殊字符),没变化就不用再画
+    rendered: Option<Appearance>,
 }
+/// 格子画出来的样子,见`Cell::rendered`
+type Appearance = (CellType, bool, bool, bool, bool, u8, Option<char>);
+/// 格子类型
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
-enum CellType {
+pub enum CellType {
     Wall,
     SnackHead,
     SnackBody,
+    /// 双人模式第二条蛇的蛇头
+    Snack2Head,
+    /// 双人模式第二条蛇的蛇身
+    Snack2Body,
     Food,
+    /// 刀刃,撞上时蛇从中间断开,丢掉后半截,剩下的太短才结束
+    Blade,
+    /// 限时出现的奖励食物
+    BonusFood,
+    /// 毒药,吃了变短扣分
+    Poison,
+    /// 传送门,从一个进去从配对的另一个出来
+    Portal,
+    /// 隔几帧挪一格的食物
+    Runner,
     Empty,
 }
+/// 食物怎么闪烁
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum FoodBlink {
+    /// 不闪烁
+    Off,
+    /// 一直闪烁
+    Always,
+    /// 只在快过期时闪烁
+    NearExpiry,
+}
+/// 方向
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
-enum Direction {
+pub enum Direction {
     Left,
     Right,
     Up,
     Down,
 }
+impl Direction {
+    /// 朝这个方向走一步的坐标变化
+    fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+        }
+    }
+    /// 反方向
+    fn opposite(self) -> Self {
+        match self {
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+        }
+    }
+}
+impl Snack {
+    /// 来回铺满棋盘的长蛇,从左上角开始一行向右、一行向左,蛇头在最后朝着还空着的格子。
+    /// 边界墙以内至少要留出蛇头前面一格和一个食物的位置
+    fn serpentine(length: usize, (width, height): (usize, usize)) -> Result<Self, GameError> {
+        let (inner_w, inner_h) = (width.saturating_sub(2), height.saturating_sub(2));
+        if length == 0 || length + 2 > inner_w * inner_h {
+            return Err(GameError::SnakeOutOfBounds);
+        }
+        //第i节的位置,从蛇尾数起
+        let cell = |i: usize| {
+            let (row, col) = (i / inner_w, i % inner_w);
+            let x = if row % 2 == 0 { 1 + col } else { inner_w - col };
+            (x, 1 + row)
+        };
+        let head = cell(length - 1);
+        let next = cell(length);
+        let direction = if next.1 > head.1 {
+            Direction::Down
+        } else if next.0 > head.0 {
+            Direction::Right
+        } else {
+            Direction::Left
+        };
+        Ok(Snack {
+            direction,
+            moved: direction,
+            head,
+            bodys: (0..length - 1).rev().map(cell).collect(),
+        })
+    }
+    /// 直线的蛇,总长度包括蛇头,蛇身沿朝向的反方向排列
+    fn straight(
+        head: (usize, usize),
+        length: usize,
+        direction: Direction,
+        (width, height): (usize, usize),
+    ) -> Result<Self, GameError> {
+        if head.0 >= width || head.1 >= height {
+            return Err(GameError::SnakeOutOfBounds);
+        }
+        let (d_x, d_y) = direction.opposite().delta();
+        let mut bodys = VecDeque::new();
+        let (mut x, mut y) = head;
+        for _ in 1..length {
+            x = x
+                .checked_add_signed(d_x)
+                .filter(|&x| x < width)
+                .ok_or(GameError::SnakeOutOfBounds)?;
+            y = y
+                .checked_add_signed(d_y)
+                .filter(|&y| y < height)
+                .ok_or(GameError::SnakeOutOfBounds)?;
+            bodys.push_back((x, y));
+        }
+        Ok(Snack {
+            direction,
+            moved: direction,
+            head,
+            bodys,
+        })
+    }
+}
+/// 游戏中发生的事件,通过`Game::on_event`注册的回调通知
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum GameEvent {
+    /// 吃到食物,score为吃完后的分数
+    FoodEaten { score: usize },
+    /// 转向
+    Turned(Direction),
+    /// 死了,cause是`StepResult::HitWall`、`StepResult::HitSelf`或`StepResult::HitBlade`,还有命时随后会复活
+    Died(StepResult),
+    /// 帧间隔变了
+    SpeedChanged(u64),
+}
+/// 一帧发生了什么
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum StepResult {
+    /// 正常移动
+    Moved,
+    /// 吃到食物
+    AteFood,
+    /// 吃到奖励食物
+    AteBonus,
+    /// 吃到毒药
+    AtePoison,
+    /// 撞墙死亡
+    HitWall,
+    /// 撞到自己死亡
+    HitSelf,
+    /// 宽限模式第一次撞墙,没有移动,下一帧还朝墙走才会死
+    WallGrace,
+    /// 复活后无敌时撞墙,没有移动也不会死,和宽限不同不停顿
+    Blocked,
+    /// 关卡模式分数够了,已经进入下一关
+    LevelUp,
+    /// 关卡模式最后一关也过了
+    Won,
+    /// 棋盘满了放不下食物,赢了
+    BoardFull,
+    /// 竞速模式达到目标分数
+    TargetReached,
+    /// 蛇长到了目标长度
+    LengthReached,
+    /// 撞上刀刃,砍断后剩下的太短,死亡
+    HitBlade,
+    /// 撞上刀刃,蛇从中间断开,丢掉了后半截
+    Cut,
+    /// 双人模式撞到对方死亡
+    HitRival,
+    /// 双人模式玩家二这一帧死了
+    RivalDied,
+    /// 死了一次,还有命,已经复活
+    LostLife,
+    /// 暂停中,没有推进
+    Paused,
+}
+/// 渲染格子需要的外观设置,每次渲染从游戏里取一份
+struct Look {
+    theme: Theme,
+    glyphs: Glyphs,
+    blink: FoodBlink,
+    background: BackgroundStyle,
+    cell_width: u16,
+    body_gradient: bool,
+    no_color: bool,
+}
 impl Cell {
     /// 渲染
-    fn render<W: Write>(&mut self, w: &mut W) -> IOResult<()> {
-        w.queue(MoveTo(self.x as u16, self.y as u16))?
-            .queue(PrintStyledContent(self.cell_style_content()))?;
+    fn render<W: Write>(
+        &mut self,
+        w: &mut W,
+        (off_x, off_y): (u16, u16),
+        look: &Look,
+        glyph: Option<char>,
+    ) -> IOResult<()> {
+        if self.rendered != Some(self.appearance(glyph)) {
+            w.queue(MoveTo(
+                self.x as u16 * look.cell_width + off_x,
+                self.y as u16 + off_y,
+            ))?;
+            self.print(w, look, glyph)?;
+        }
         self.changed_flag = false;
         Ok(())
     }
-    fn cell_style_content(&mut self) -> StyledContent<char> {
-        match self.cell_type {
-            CellType::Wall => '█'.blue().on_black(),
-            CellType::SnackHead => '#'.green().on_black(),
-            CellType::SnackBody => '#'.yellow().on_black(),
-            CellType::Food => '$'.red().on_black().slow_blink(),
-            CellType::Empty => '█'.black().on_black(),
+    /// 在光标当前位置画出格子,不管有没有变,按区域重画时一行连着画
+    fn print<W: Write>(&mut self, w: &mut W, look: &Look, glyph: Option<char>) -> IOResult<()> {
+        let content = self.cell_style_content(look, glyph);
+        //方格显示时一格画两个字符
+        let text = content
+            .content()
+            .to_string()
+            .repeat(look.cell_width as usize);
+        w.queue(PrintStyledContent(paint(
+            look.no_color,
+            StyledContent::new(*content.style(), text),
+        )))?;
+        self.rendered = Some(self.appearance(glyph));
+        self.changed_flag = false;
+        Ok(())
+    }
+    fn appearance(&self, glyph: Option<char>) -> Appearance {
+        (
+            self.cell_type,
+            self.faded,
+            self.highlighted,
+            self.tail,
+            self.expiring,
+            self.shade,
+            glyph,
+        )
+    }
+    /// 交给其他输出后端画,没变的不画
+    fn draw(&mut self, renderer: &mut dyn Renderer) {
+        let appearance = self.appearance(None);
+        if self.rendered != Some(appearance) {
+            renderer.draw_cell(self.x, self.y, self.cell_type);
+            self.rendered = Some(appearance);
+        }
+        self.changed_flag = false;
+    }
+    /// glyph是不按格子类型显示的字符
+    fn cell_style_content(&mut self, look: &Look, glyph: Option<char>) -> StyledContent<char> {
+        let Look {
+            theme,
+            glyphs,
+            blink,
+            background,
+            body_gradient,
+            ..
+        } = look;
+        //变暗的空格子是走法提示或蛇尾的残影
+        let shown = if self.cell_type == CellType::Empty && self.faded {
+            CellType::SnackBody
+        } else {
+            self.cell_type
+        };
+        let tail = shown == CellType::SnackBody && self.tail;
+        let glyph = match glyph {
+            Some(glyph) => glyph,
+            None if tail => glyphs.snack_tail,
+            None => glyphs.get(shown),
+        };
+        let color = if tail {
+            theme.snack_tail
+        } else {
+            theme.color(shown)
+        };
+        let content = glyph.with(color).on(theme.background);
+        let content = match shown {
+            CellType::Food | CellType::BonusFood
+                if *blink == FoodBlink::Always
+                    || *blink == FoodBlink::NearExpiry && self.expiring =>
+            {
+                content.slow_blink()
+            }
+            CellType::Empty => background.empty((self.x, self.y), content, theme),
+            _ => content,
+        };
+        if self.highlighted {
+            content.red().bold()
+        } else if self.faded {
+            content.dim()
+        } else if *body_gradient && shown == CellType::SnackBody {
+            //靠近蛇头的亮,越往蛇尾越暗
+            match self.shade {
+                0 => content.bold(),
+                1 => content,
+                _ => content.dim(),
+            }
+        } else {
+            content
         }
     }
-    fn set_type(&mut self, t: CellType) {
-        self.changed_flag = true;
+    /// 标记为需要渲染,之前没标记过时返回true,调用方要把它加进待渲染列表
+    fn mark_changed(&mut self) -> bool {
+        !std::mem::replace(&mut self.changed_flag, true)
+    }
+    fn set_type(&mut self, t: CellType) -> bool {
         self.cell_type = t;
+        self.faded = false;
+        self.tail = false;
+        self.expiring = false;
+        self.mark_changed()
+    }
+    /// 下次渲染时强制重画
+    fn invalidate(&mut self) -> bool {
+        self.rendered = None;
+        self.mark_changed()
+    }
+    fn set_faded(&mut self, faded: bool) -> bool {
+        if self.faded != faded {
+            self.faded = faded;
+            self.mark_changed()
+        } else {
+            false
+        }
+    }
+    fn set_highlighted(&mut self, highlighted: bool) -> bool {
+        if self.highlighted != highlighted {
+            self.highlighted = highlighted;
+            self.mark_changed()
+        } else {
+            false
+        }
+    }
+    fn set_shade(&mut self, shade: u8) -> bool {
+        if self.shade != shade {
+            self.shade = shade;
+            self.mark_changed()
+        } else {
+            false
+        }
+    }
+    fn set_tail(&mut self, tail: bool) -> bool {
+        if self.tail != tail {
+            self.tail = tail;
+            self.mark_changed()
+        } else {
+            false
+        }
+    }
+    fn set_expiring(&mut self, expiring: bool) -> bool {
+        if self.expiring != expiring {
+            self.expiring = expiring;
+            self.mark_changed()
+        } else {
+            false
+        }
+    }
+}
+impl Snack {
+    /// 开局时的蛇
+    fn new() -> Self {
+        Snack {
+            direction: Direction::Right,
+            moved: Direction::Right,
+            head: (9, 7),
+            bodys: {
+                let mut bodys = VecDeque::new();
+                bodys.push_back((8, 7));
+                bodys.push_back((8, 8));
+                bodys.push_back((7, 8));
+                bodys.push_back((7, 9));
+                bodys.push_back((7, 10));
+                bodys.push_back((8, 10));
+                bodys.push_back((8, 11));
+                bodys
+            },
+        }
     }
 }
 impl Game {
     /// 创建初始化
-    pub fn new() -> Result<Self, &'static str> {
-        if let Ok((x, y)) = terminal::size() {
-            if x < 60 || y < 20 {
-                Err("窗口尺寸过小")
-            } else {
-                let mut cells = Vec::with_capacity(x as usize);
-                for i in 0..x {
-                    let mut columns = Vec::with_capacity(y as usize);
-                    for j in 0..y {
-                        columns.push(Cell {
-                            x: i as usize,
-                            y: j as usize,
-                            changed_flag: false,
-                            cell_type: CellType::Empty,
-                        })
-                    }
-                    cells.push(columns);
-                }
-                let direction = Direction::Right;
-                let snack = Snack {
-                    direction,
-                    head: (9, 7),
-                    bodys: {
-                        let mut bodys = LinkedList::new();
-                        bodys.push_back((8, 7));
-                        bodys.push_back((8, 8));
-                        bodys.push_back((7, 8));
-                        bodys.push_back((7, 9));
-                        bodys.push_back((7, 10));
-                        bodys.push_back((8, 10));
-                        bodys.push_back((8, 11));
-                        bodys
-                    },
-                };
-                Ok(Game {
-                    writer: Box::new(stdout()),
-                    cells,
-                    snack,
-                    score: 0,
-                    speed: 80,
-                })
-            }
-        } else {
-            Err("初始化失败:无法获取窗口尺寸")
-        }
+    pub fn new() -> Result<Self, GameError> {
+        GameConfig::new().build()
+    }
+    /// 指定棋盘尺寸,不能超过终端窗口
+    pub fn with_size(width: usize, height: usize) -> Result<Self, GameError> {
+        GameConfig::new().size(width, height).build()
+    }
+    /// 在终端中按宽高比选择最大的棋盘并居中
+    pub fn with_auto_size(auto: AutoSize) -> Result<Self, GameError> {
+        GameConfig::new().auto_size(auto).build()
+    }
+    /// 按名字加载内置关卡,classic、donut、cross或spiral,见`level_names`,在终端中居中
+    pub fn with_level(name: &str) -> Result<Self, GameError> {
+        GameConfig::new().level(name).build()
+    }
+    /// 固定随机数种子,相同种子生成相同的食物序列
+    pub fn with_seed(seed: u64) -> Result<Self, GameError> {
+        GameConfig::new().seed(seed).build()
+    }
+    /// 从文本地图创建,`#`是墙,`.`是空地,`S`是蛇头起点,`X`是刀刃,相同的数字是一对传送门
+    pub fn from_map(map: &str) -> Result<Self, GameError> {
+        GameConfig::new().map(map).build()
+    }
+}
+impl<W: Write> Game<W> {
+    /// 输出到任意writer,不读取终端尺寸,方便测试
+    pub fn with_writer(writer: W, width: usize, height: usize) -> Result<Self, GameError> {
+        GameConfig::new()
+            .size(width, height)
+            .build_with_writer(writer)
+    }
+    /// snack是开局的蛇,None时用默认的
+    fn build(
+        writer: W,
+        x: usize,
+        y: usize,
+        offset: (u16, u16),
+        snack: Option<Snack>,
+    ) -> Result<Self, GameError> {
+        validate_dimensions(x, y, snack.as_ref())?;
+        let cells = new_cells(x, y);
+        let snack = snack.unwrap_or_else(Snack::new);
+        Ok(Game {
+            writer,
+            cells,
+            snack: snack.clone(),
+            score: 0,
+            speed: INITIAL_SPEED,
+            initial_speed: INITIAL_SPEED,
+            ticks: 0,
+            first_food_delay: 0,
+            food_distances: HashMap::new(),
+            multi_food_bonus: None,
+            offset,
+            move_hints: false,
+            move_hint: None,
+            distinct_tail: false,
+            tail_cell: None,
+            initial_snack: snack,
+            map_walls: None,
+            lap_bonus: 0,
+            last_wrap: None,
+            lap_message: 0,
+            map_blades: Vec::new(),
+            portals: Vec::new(),
+            debug_overlay: false,
+            recording: None,
+            wall_grace: false,
+            grace_wall: None,
+            wall_hug_every: None,
+            wall_hug_frames: 0,
+            wall_hug_bonus: 0,
+            wrap_cost: 0,
+            wrap_flash: None,
+            levels: Vec::new(),
+            level: 0,
+            target_score: None,
+            target_length: None,
+            bonus_chance: BONUS_CHANCE,
+            runner: None,
+            runner_every: None,
+            speed_curve: None,
+            run_start: None,
+            input_source: None,
+            food_spawner: None,
+            renderer: None,
+            catch_up_frames: 0,
+            last_eat_tick: None,
+            combo: 1,
+            blade_min_length: BLADE_MIN_LENGTH,
+            blink_warning: None,
+            input_queue: VecDeque::with_capacity(INPUT_QUEUE_LEN),
+            max_input_age_ticks: None,
+            input_priority: InputPriority::Buffered,
+            input_key: None,
+            session: GameStats::default(),
+            stats: Stats::default(),
+            track_slow_frames: false,
+            slow_frames: 0,
+            foods: Vec::new(),
+            food_lifetime: None,
+            food_points: HashMap::new(),
+            food_values: None,
+            food_count: 1,
+            initial_food: 1,
+            growth_per_food: 1,
+            pending_growth: 0,
+            growth_delay: 0,
+            delayed_growth: VecDeque::new(),
+            speed_keys: true,
+            menu: false,
+            sound: false,
+            ghost_trail: false,
+            ghost_file: None,
+            ghost: Vec::new(),
+            ghost_path: Vec::new(),
+            ghost_shown: Vec::new(),
+            trail: Vec::new(),
+            key_bindings: KeyBindings::classic(),
+            rival: None,
+            bigger_eats_smaller: false,
+            head_on: HeadOn::BothDie,
+            on_event: None,
+            food_decay: None,
+            food_wall_clearance: 0,
+            food_head_clearance: 0,
+            paused: false,
+            focus_paused: false,
+            confirm_quit: false,
+            quit_prompt: None,
+            border: BorderMode::walls(),
+            seed: None,
+            daily: None,
+            rng: ChaCha12Rng::from_entropy(),
+            bonus: None,
+            poison: None,
+            lives: 1,
+            initial_lives: 1,
+            wall_costs_life: false,
+            respawn_flash: 0,
+            mercy_frames: 0,
+            invuln_frames: 0,
+            self_overlap: 0,
+            time_limit: None,
+            theme: Theme::classic(),
+            glyphs: Glyphs::block(),
+            blink_food: true,
+            background: BackgroundStyle::Solid,
+            cell_width: 1,
+            hud_below: false,
+            countdown: (3, COUNTDOWN_STEP),
+            hazard_every: None,
+            next_hazard: 0,
+            shrink_every: None,
+            next_shrink: Duration::ZERO,
+            shrink_depth: 0,
+            shrink_pending: Vec::new(),
+            self_collision: true,
+            tail_chasing: true,
+            invert_controls: false,
+            body_gradient: false,
+            assist: false,
+            ruler: false,
+            no_color: false,
+            edge_warning: false,
+            warned_wall: None,
+            death_animation: true,
+            wait_for_input: false,
+            opening_input: OpeningInput::LastDirection,
+            hud: Vec::new(),
+            frame_log: None,
+            autosave: None,
+            leaderboard_file: None,
+            leaderboard: Vec::new(),
+            zoom: None,
+            in_alt_screen: false,
+            panel: None,
+            alternate_screen: true,
+            autopilot: false,
+            loaded: false,
+            dirty: Vec::new(),
+            empty_scratch: Vec::new(),
+        })
     }
     /// 构建场景,可以定义其他场景
-    fn build_default(&mut self) -> IOResult<()> {
-        self.writer
-            .queue(EnterAlternateScreen)?
-            .queue(SetTitle("Snack"))?
-            .queue(Hide)?
-            .flush()?;
+    fn build_default(&mut self) -> Result<(), GameError> {
         let width = self.cells.len();
         let height = self.cells[0].len();
-        // wall
-        self.cells.iter_mut().enumerate().for_each(|(x, column)| {
-            column.iter_mut().enumerate().for_each(|(y, cell)| {
-                if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
-                    cell.cell_type = CellType::Wall;
-                }
-            })
-        });
+        //按整个棋盘预留好,之后每帧要重画的格子不用再扩容
+        self.dirty.reserve(width * height);
+        // wall,自定义地图按地图放,否则能穿过的边以外都有边界
+        if let Some(walls) = &self.map_walls {
+            for &(x, y) in walls {
+                self.cells[x][y].cell_type = CellType::Wall;
+            }
+        } else {
+            let border = self.border;
+            self.cells.iter_mut().enumerate().for_each(|(x, column)| {
+                column.iter_mut().enumerate().for_each(|(y, cell)| {
+                    if border.is_wall((x, y), (width, height), 1) {
+                        cell.cell_type = CellType::Wall;
+                    }
+                })
+            });
+        }
+        for &(x, y) in self.map_blades.iter() {
+            self.cells[x][y].cell_type = CellType::Blade;
+        }
+        for &(a, b) in self.portals.iter() {
+            for (x, y) in [a, b] {
+                self.cells[x][y].cell_type = CellType::Portal;
+            }
+        }
         // snack
         let (head_x, head_y) = self.snack.head;
         self.cells[head_x][head_y].cell_type = CellType::SnackHead;
@@ -152,210 +1186,3157 @@ impl Game {
             .bodys
             .iter()
             .for_each(|&(x, y)| self.cells[x][y].cell_type = CellType::SnackBody);
-        // food
-        self.generage_food()?;
+        if let Some(rival) = &self.rival {
+            let (x, y) = rival.snack.head;
+            self.cells[x][y].cell_type = CellType::Snack2Head;
+            for &(x, y) in rival.snack.bodys.iter() {
+                self.cells[x][y].cell_type = CellType::Snack2Body;
+            }
+        }
+        // food,设置了延迟则在游戏循环中生成
+        if self.first_food_delay == 0 && !self.fill_initial_food() {
+            return Err(GameError::NoSpaceForFood);
+        }
+        self.spawn_runner();
         Ok(())
     }
-    pub fn run(&mut self) -> IOResult<()> {
-        //使用原始模式,这会禁用相关快捷键
-        enable_raw_mode()?;
-        //使用默认场景
-        self.build_default()?;
-        //渲染整个画面
-        self.render_all()?;
-        //title
+    /// 场景布置后(x, y)是不是墙
+    fn is_layout_wall(&self, x: usize, y: usize) -> bool {
+        let (width, height) = self.size();
+        match &self.map_walls {
+            Some(walls) => walls.contains(&(x, y)),
+            None => self.border.is_wall((x, y), (width, height), 1),
+        }
+    }
+    /// 检查开局的布局:蛇不能压在墙或传送门上,棋盘上还要留得下第一个食物
+    pub(crate) fn check_layout(&self) -> Result<(), GameError> {
+        let blocked = |(x, y)| {
+            self.is_layout_wall(x, y)
+                || self
+                    .portals
+                    .iter()
+                    .any(|&(a, b)| a == (x, y) || b == (x, y))
+        };
+        let snake = self.snake_cells().collect::<Vec<_>>();
+        if snake.iter().any(|&pos| blocked(pos)) {
+            return Err(GameError::SnakeOnWall);
+        }
+        let (width, height) = self.size();
+        let has_space = (0..width)
+            .flat_map(|x| (0..height).map(move |y| (x, y)))
+            .any(|pos| !blocked(pos) && !snake.contains(&pos));
+        if !has_space {
+            return Err(GameError::NoSpaceForFood);
+        }
+        Ok(())
+    }
+    /// 开始游戏,返回最后一局结束的原因和得分
+    pub fn run(&mut self) -> Result<(GameOutcome, usize), GameError> {
+        //panic时先恢复终端再输出panic信息,正常退出后换回原来的hook
+        let prev_hook = Arc::new(panic::take_hook());
+        let hook = Arc::clone(&prev_hook);
+        let alternate_screen = self.alternate_screen;
+        panic::set_hook(Box::new(move |info| {
+            restore_terminal(alternate_screen);
+            hook(info);
+        }));
+        //终端状态跟着守卫走,返回前一定恢复,调用方才能看到错误信息
+        let result = SignalGuard::install(alternate_screen)
+            .and_then(|signals| Ok((signals, Terminal::enter(self)?)))
+            .map_err(GameError::from)
+            .and_then(|(_signals, terminal)| terminal.game.run_inner());
+        drop(panic::take_hook());
+        if let Ok(prev_hook) = Arc::try_unwrap(prev_hook) {
+            panic::set_hook(prev_hook);
+        }
+        result
+    }
+    fn run_inner(&mut self) -> Result<(GameOutcome, usize), GameError> {
+        //开始菜单只能用键盘操作
+        if self.menu && self.input_source.is_none() && !self.menu()? {
+            return Ok((GameOutcome::Quit, self.score));
+        }
+        loop {
+            //使用默认场景,读档的第一局保持存档的局面
+            if !std::mem::take(&mut self.loaded) {
+                self.build_default()?;
+            }
+            //渲染整个画面,菜单和结束面板都清掉
+            self.request_full_redraw()?;
+            let result = self.poll(); //开始游戏进程
+            self.session.games_played += 1;
+            self.session.best_score = self.session.best_score.max(self.score);
+            let outcome = result?;
+            self.save_ghost()?;
+            if outcome != GameOutcome::Quit {
+                self.record_score(self.score)?;
+            }
+            //死了可以重新开始,退出和时间到直接结束
+            if matches!(outcome, GameOutcome::Quit | GameOutcome::TimeUp)
+                || !self.show_game_over(&outcome, self.score)?
+            {
+                return Ok((outcome, self.score));
+            }
+            self.reset();
+        }
+    }
+    /// 离开备用屏幕并显示光标,已经离开过就什么也不做;
+    /// 不用备用屏幕时光标移到画面下面一行,画面留着,shell提示符接在后面
+    fn leave_screen(&mut self) -> IOResult<()> {
+        if self.in_alt_screen {
+            self.in_alt_screen = false;
+            self.writer.queue(DisableFocusChange)?;
+            if self.alternate_screen {
+                self.writer.queue(LeaveAlternateScreen)?;
+            } else {
+                let (_, height) = self.screen_size();
+                let bottom = self.hud_row(height) + self.offset.1;
+                self.writer.queue(MoveTo(0, bottom))?.queue(Print("\r\n"))?;
+            }
+            self.writer.queue(Show)?.flush()?;
+        }
+        Ok(())
+    }
+    /// 开始菜单,上下选择,左右切换,回车开始返回true,Esc返回false
+    fn menu(&mut self) -> Result<bool, GameError> {
+        let (mut row, mut mode, mut level) = (0, 0, 1);
+        loop {
+            self.print_menu(row, mode, level)?;
+            if let Event::Key(key_event) = read()? {
+                if key_event.kind == KeyEventKind::Release {
+                    continue;
+                }
+                if key_event.code == KeyCode::Enter {
+                    break;
+                }
+                let action = self.key_bindings.action(key_event.code);
+                if action == Some(Action::Quit) {
+                    return Ok(false);
+                }
+                let (choice, len) = if row == 0 {
+                    (&mut mode, MENU_MODES.len())
+                } else {
+                    (&mut level, MENU_LEVELS.len())
+                };
+                match action {
+                    Some(Action::Turn(Direction::Up | Direction::Down)) => row = 1 - row,
+                    Some(Action::Turn(Direction::Left)) => *choice = (*choice + len - 1) % len,
+                    Some(Action::Turn(Direction::Right)) => *choice = (*choice + 1) % len,
+                    _ => {}
+                }
+            }
+        }
+        self.border = if mode == 1 {
+            BorderMode::wrap()
+        } else {
+            BorderMode::walls()
+        };
+        self.time_limit = (mode == 2).then_some(MENU_TIME_LIMIT);
+        self.initial_speed = MENU_LEVELS[level].1;
+        self.speed = self.initial_speed;
+        Ok(true)
+    }
+    /// 画开始菜单,选中的一行高亮
+    fn print_menu(&mut self, row: usize, mode: usize, level: usize) -> IOResult<()> {
+        let lines = [
+            "贪吃蛇".to_string(),
+            format!("模式: < {} >", MENU_MODES[mode]),
+            format!("难度: < {} >", MENU_LEVELS[level].0),
+            "回车开始 Esc退出".to_string(),
+        ];
+        let (width, height) = self.screen_size();
+        let width = width as u16;
+        let top = (height as u16).saturating_sub(lines.len() as u16) / 2;
+        for (i, line) in lines.iter().enumerate() {
+            //没有颜色时选中的一行看不出来,前面加个箭头
+            let line = &if self.no_color && i == row + 1 {
+                format!("> {}", line)
+            } else {
+                line.clone()
+            };
+            let content = if i == row + 1 {
+                line.clone().black().on_yellow()
+            } else {
+                line.clone().white().on_black()
+            };
+            self.writer
+                .queue(MoveTo(
+                    width.saturating_sub(display_width(line)) / 2 + self.offset.0,
+                    top + i as u16 + self.offset.1,
+                ))?
+                .queue(PrintStyledContent(paint(self.no_color, content)))?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+    /// 一局结束后居中显示结束面板和得分,按重新开始的键返回true,按退出的键返回false
+    fn show_game_over(&mut self, cause: &GameOutcome, score: usize) -> IOResult<bool> {
+        let title = if cause.is_win() {
+            format!("{}!", cause)
+        } else {
+            format!("游戏结束 - {}", cause)
+        };
+        let mut lines = vec![
+            title,
+            format!("得分: {}", score),
+            format!(
+                "吃了{}个食物 转向{}次 坚持{}帧 用时{:.1}秒",
+                self.stats.food_eaten,
+                self.stats.turns,
+                self.stats.frames,
+                self.stats.play_time.as_secs_f64()
+            ),
+        ];
+        if !self.leaderboard.is_empty() {
+            lines.push("排行榜".to_string());
+            for (i, entry) in self.leaderboard.iter().take(LEADERBOARD_SHOWN).enumerate() {
+                lines.push(format!(
+                    "{}. {} {} {}",
+                    i + 1,
+                    entry.name,
+                    entry.score,
+                    entry.date_string()
+                ));
+            }
+        }
+        let key = |action| self.key_bindings.first_key(action).map(key_label);
+        let hint = [
+            key(Action::Restart).map(|k| format!("按{}重新开始", k)),
+            key(Action::Quit).map(|k| format!("按{}退出", k)),
+        ];
+        lines.push(hint.into_iter().flatten().collect::<Vec<_>>().join(" "));
+        //面板比最长的一行左右各宽2格,上下各空一行
+        let inner = lines
+            .iter()
+            .map(|line| display_width(line))
+            .max()
+            .unwrap_or(0)
+            + 4;
+        let (width, height) = self.screen_size();
+        let (width, height) = (width as u16, height as u16);
+        let left = width.saturating_sub(inner) / 2 + self.offset.0;
+        let top = height.saturating_sub(lines.len() as u16 + 2) / 2 + self.offset.1;
+        let blank = " ".repeat(inner as usize);
+        self.writer
+            .queue(MoveTo(left, top))?
+            .queue(PrintStyledContent(paint(
+                self.no_color,
+                blank.clone().on_dark_grey(),
+            )))?;
+        for (i, line) in lines.iter().enumerate() {
+            let pad = inner - display_width(line);
+            let text = format!(
+                "{}{}{}",
+                " ".repeat((pad / 2) as usize),
+                line,
+                " ".repeat((pad - pad / 2) as usize)
+            );
+            let text = match i {
+                0 if cause.is_win() => text.green().on_dark_grey().bold(),
+                0 => text.red().on_dark_grey().bold(),
+                1 => text.yellow().on_dark_grey().bold(),
+                _ => text.white().on_dark_grey(),
+            };
+            self.writer
+                .queue(MoveTo(left, top + 1 + i as u16))?
+                .queue(PrintStyledContent(paint(self.no_color, text)))?;
+        }
         self.writer
-            .queue(MoveTo((self.cells.len() as u16 - 6) / 2, 0))?
-            .queue(PrintStyledContent("贪吃蛇".green().on_black()))?
+            .queue(MoveTo(left, top + 1 + lines.len() as u16))?
+            .queue(PrintStyledContent(paint(
+                self.no_color,
+                blank.on_dark_grey(),
+            )))?
             .flush()?;
-        //score and tip
-        self.print_score()?;
-        self.poll()?; //开始游戏进程
-        Ok(())
+        //不用键盘时没法选择重新开始
+        if self.input_source.is_some() {
+            return Ok(false);
+        }
+        //丢掉游戏中按下还没处理的键,免得误触重新开始
+        while poll(Duration::ZERO)? {
+            read()?;
+        }
+        loop {
+            if let Event::Key(key_event) = read()? {
+                match self.key_bindings.action(key_event.code) {
+                    Some(Action::Restart) => return Ok(true),
+                    Some(Action::Quit) => return Ok(false),
+                    _ => {}
+                }
+            }
+        }
     }
-    ///游戏循环
-    fn poll(&mut self) -> IOResult<()> {
-        thread::sleep(Duration::from_secs(2));
-        let mut tmp_key = KeyEvent::new_with_kind(
-            KeyCode::Char('d'),
-            KeyModifiers::empty(),
-            KeyEventKind::Release,
-        );
+    /// 重置到开局状态
+    fn reset(&mut self) {
+        for cell in self.cells.iter_mut().flatten() {
+            cell.cell_type = CellType::Empty;
+            cell.faded = false;
+            cell.tail = false;
+            cell.expiring = false;
+            cell.highlighted = false;
+            cell.invalidate();
+        }
+        //开局会重画全部格子
+        self.dirty.clear();
+        self.snack = self.initial_snack.clone();
+        self.score = 0;
+        self.speed = self.initial_speed;
+        self.ticks = 0;
+        self.slow_frames = 0;
+        self.pending_growth = 0;
+        self.delayed_growth.clear();
+        self.grace_wall = None;
+        self.wrap_flash = None;
+        self.warned_wall = None;
+        self.run_start = None;
+        self.last_eat_tick = None;
+        self.combo = 1;
+        self.next_hazard = self.hazard_every.unwrap_or(0);
+        self.reset_shrink();
+        //关卡模式从第一关重新开始
+        if self.level != 0 {
+            self.level = 0;
+            self.use_level_layout();
+            self.snack = self.initial_snack.clone();
+        }
+        if let Some(recording) = self.recording.as_mut() {
+            recording.clear();
+        }
+        self.foods.clear();
+        self.food_distances.clear();
+        self.food_points.clear();
+        self.bonus = None;
+        self.poison = None;
+        self.runner = None;
+        self.trail.clear();
+        self.ghost_path.clear();
+        self.ghost_shown.clear();
+        self.wall_hug_frames = 0;
+        self.wall_hug_bonus = 0;
+        self.lives = self.initial_lives;
+        self.respawn_flash = 0;
+        self.invuln_frames = 0;
+        self.self_overlap = 0;
+        self.stats = Stats::default();
+        self.paused = false;
+        self.move_hint = None;
+        self.tail_cell = None;
+        self.focus_paused = false;
+        self.quit_prompt = None;
+        self.input_queue.clear();
+        self.input_key = None;
+        self.last_wrap = None;
+        self.lap_message = 0;
+        if let Some(rival) = self.rival.as_mut() {
+            rival.reset();
+        }
+        if let Some(seed) = self.seed {
+            self.rng = ChaCha12Rng::seed_from_u64(seed);
+        }
+    }
+    ///游戏循环,死亡时返回死因
+    fn poll(&mut self) -> Result<GameOutcome, GameError> {
+        if !self.countdown()? {
+            return Ok(GameOutcome::Quit);
+        }
+        if self.opening_input == OpeningInput::Ignore {
+            self.drain_input()?;
+        }
+        let mut last_frame = Instant::now();
+        //下一次移动的时间,在这之前一直读输入
+        let mut next_tick = last_frame;
+        let mut shown_secs = self.remaining_secs();
+        let mut shown_tenths = None;
+        let mut shown_play_secs = 0;
+        //开局的方向,等待第一次输入时一开始是None
+        let mut start = self.start_direction()?;
+        let mut last_autosave = Instant::now();
         loop {
-            if poll(Duration::from_millis(0))? {
-                match read().unwrap() {
-                    crossterm::event::Event::Key(key_event) => {
-                        if key_event.kind == KeyEventKind::Release {
-                            tmp_key = key_event;
-                        }
-                        // 按下escape会退出游戏循环
-                        if let KeyCode::Esc = key_event.code {
-                            self.writer.execute(LeaveAlternateScreen)?;
-                            break;
+            let timeout = if self.paused || start.is_none() {
+                Duration::from_millis(10)
+            } else {
+                next_tick.saturating_duration_since(Instant::now())
+            };
+            //有其他输入来源时不读终端事件,只等到下一帧
+            let has_event = if self.input_source.is_some() {
+                thread::sleep(timeout);
+                false
+            } else {
+                poll(timeout)?
+            };
+            if has_event {
+                let event = read()?;
+                //失去焦点自动暂停,回来时只恢复自动暂停的,不支持焦点事件的终端不会发这两个事件
+                match event {
+                    Event::FocusLost if !self.paused => {
+                        self.paused = true;
+                        self.focus_paused = true;
+                        self.print_paused()?;
+                    }
+                    Event::FocusGained => {
+                        if self.focus_paused {
+                            self.paused = false;
+                            self.focus_paused = false;
                         }
+                        //切走期间终端内容可能被弄乱了
+                        self.request_full_redraw()?;
                     }
+                    Event::Resize(..) => self.request_full_redraw()?,
                     _ => {}
                 }
+                if let Event::Key(key_event) = event {
+                    if let Some(outcome) = self.handle_key(key_event, &mut next_tick)? {
+                        return Ok(outcome);
+                    }
+                }
+            } else if self.paused {
+                //暂停时只读输入,不推进游戏,也不计时
+                last_frame = Instant::now();
+                next_tick = last_frame;
+            } else if start.is_none() {
+                //还没按方向键,同样不推进游戏也不计时
+                start = self.take_start_direction()?;
+                last_frame = Instant::now();
+                next_tick = last_frame;
             } else {
-                if let KeyCode::Char(c) = tmp_key.code {
-                    match c {
-                        'a' | 'A' => self.turn_around(Direction::Left),
-                        's' | 'S' => self.turn_around(Direction::Down),
-                        'd' | 'D' => self.turn_around(Direction::Right),
-                        'w' | 'W' => self.turn_around(Direction::Up),
-                        _ => {}
+                let frame_start = Instant::now();
+                let frame_time = frame_start - last_frame;
+                self.stats.play_time += frame_time;
+                last_frame = frame_start;
+                //限时模式时间到了正常结束
+                if let Some(secs) = self.remaining_secs() {
+                    if secs == 0 {
+                        return Ok(GameOutcome::TimeUp);
                     }
+                    if shown_secs != Some(secs) {
+                        shown_secs = Some(secs);
+                        self.print_score()?;
+                    }
+                }
+                //大分数面板每秒刷新一次用时
+                let play_secs = self.stats.play_time.as_secs();
+                if self.panel.is_some() && play_secs != shown_play_secs {
+                    shown_play_secs = play_secs;
+                    self.print_panel()?;
+                }
+                //竞速模式每0.1秒刷新一次用时
+                let tenths = self.speedrun_time().map(|time| time.as_millis() / 100);
+                if tenths.is_some() && tenths != shown_tenths {
+                    shown_tenths = tenths;
+                    self.print_score()?;
+                }
+                if self
+                    .input_source
+                    .as_ref()
+                    .is_some_and(|source| source.should_quit())
+                {
+                    return Ok(GameOutcome::Quit);
                 }
-                // 处理下一帧
-                match self.collision_detection() {
-                    (CellType::Wall, _) => {
-                        //在这停顿，死个明白
-                        thread::sleep(Duration::from_secs(2));
-                        self.writer.execute(LeaveAlternateScreen).unwrap();
-                        return Err(Error::new(ErrorKind::NotFound, "撞墙"));
+                self.check_autosave(&mut last_autosave)?;
+                let hud = (self.score, self.lives, self.lap_message > 0);
+                //落后了就多推进几帧逻辑再渲染一次,最多补catch_up_frames帧
+                let behind = frame_start.saturating_duration_since(next_tick).as_millis()
+                    / u128::from(self.speed.max(1));
+                let steps = 1 + (behind as usize).min(self.catch_up_frames);
+                let mut stepped = 0;
+                while stepped < steps {
+                    stepped += 1;
+                    if let Some(outcome) = self.tick(&mut next_tick)? {
+                        return self.end_game(outcome);
                     }
-                    (CellType::SnackHead, _) => {}
-                    (CellType::SnackBody, _) => {
-                        thread::sleep(Duration::from_secs(2));
-                        self.writer.execute(LeaveAlternateScreen).unwrap();
-                        return Err(Error::new(ErrorKind::NotFound, "自杀"));
+                    //宽限的那一帧要让玩家看到,不再往后补
+                    if self.grace_wall.is_some() {
+                        break;
                     }
-                    (CellType::Food, (x, y)) => self.eat_food(x, y)?,
-                    (CellType::Empty, (x, y)) => self.go(x, y),
-                };
-                //渲染更新的部分
-                self.render_only_updated()?;
-                thread::sleep(Duration::from_millis(self.speed));
+                }
+                if (self.score, self.lives, self.lap_message > 0) != hud {
+                    self.print_score()?;
+                }
+                //渲染更新的部分,关闭调试信息时不计时
+                if self.debug_overlay {
+                    let dirty = self.dirty.len();
+                    let render_start = Instant::now();
+                    self.render_only_updated()?;
+                    self.print_debug(frame_time, render_start.elapsed(), dirty)?;
+                } else {
+                    self.render_only_updated()?;
+                }
+                if self.track_slow_frames
+                    && frame_start.elapsed() > Duration::from_millis(self.speed)
+                {
+                    self.slow_frames += 1;
+                }
+                //补不上的帧直接丢掉,从现在重新计时
+                next_tick = (next_tick + Duration::from_millis(self.speed) * stepped as u32)
+                    .max(Instant::now());
             }
         }
-        Ok(())
-    }
-    pub fn score(&self) -> usize {
-        self.score
-    }
-    /// 底部展示分数
-    fn print_score(&mut self) -> IOResult<()> {
-        self.writer
-            .queue(MoveTo(4, self.cells[0].len() as u16 - 1))?
-            .queue(PrintStyledContent("得分:".on_blue()))?
-            .queue(PrintStyledContent(
-                format!("{:^7?}", self.score).green().on_white().bold(),
-            ))?
-            .queue(MoveRight(4))?
-            .queue(PrintStyledContent("Speed: ".on_blue()))?
-            .queue(PrintStyledContent(self.speed.to_string().red().on_white()))?
-            .queue(MoveRight(4))?
-            .queue(PrintStyledContent("按Esc退出".grey().on_blue()))?
-            .flush()?;
-
-        Ok(())
     }
-    ///设置转弯
-    fn turn_around(&mut self, dir: Direction) {
-        //只有拐弯的命令才会被处理
-        if let (Direction::Left, Direction::Up)
-        | (Direction::Left, Direction::Down)
-        | (Direction::Right, Direction::Up)
-        | (Direction::Right, Direction::Down)
-        | (Direction::Up, Direction::Left)
-        | (Direction::Up, Direction::Right)
-        | (Direction::Down, Direction::Left)
-        | (Direction::Down, Direction::Right) = (self.snack.direction, dir)
-        {
-            self.snack.direction = dir;
+    /// 游戏中按了一个键:转向在按下和长按重复时都生效,其他操作只在按下时生效,
+    /// 松开一律不处理,很多终端根本不发送松开事件;退出或按.单步时一局结束,返回结束原因
+    fn handle_key(
+        &mut self,
+        key_event: KeyEvent,
+        next_tick: &mut Instant,
+    ) -> Result<Option<GameOutcome>, GameError> {
+        //询问退出时只认确认和取消,其他键都忽略
+        if let Some(was_paused) = self.quit_prompt {
+            if key_event.kind == KeyEventKind::Press {
+                match self.key_bindings.action(key_event.code) {
+                    Some(Action::Confirm) => return Ok(Some(GameOutcome::Quit)),
+                    Some(Action::Cancel) => {
+                        self.quit_prompt = None;
+                        self.paused = was_paused;
+                        self.print_paused()?;
+                    }
+                    _ => {}
+                }
+            }
+            return Ok(None);
+        }
+        let action = self.key_bindings.action(key_event.code);
+        //按下和长按重复都会转向,松开不处理,很多终端根本不发送松开事件
+        if key_event.kind != KeyEventKind::Release {
+            match action {
+                Some(Action::Turn(dir)) => self.queue_key_turn(dir, key_event.code),
+                Some(Action::Turn2(dir)) => self.queue_rival_direction(dir),
+                _ => {}
+            }
+        }
+        // 退出游戏循环
+        if action == Some(Action::Quit) {
+            if !self.confirm_quit {
+                return Ok(Some(GameOutcome::Quit));
+            }
+            if key_event.kind == KeyEventKind::Press {
+                //暂停后再询问,回答n时恢复原来的暂停状态
+                self.quit_prompt = Some(self.paused);
+                self.paused = true;
+                self.print_paused()?;
+            }
+            return Ok(None);
         }
+        //其他操作只在按下时生效
+        if key_event.kind != KeyEventKind::Press {
+            return Ok(None);
+        }
+        match action {
+            // 暂停或继续
+            Some(Action::Pause) => self.set_paused(!self.paused)?,
+            // 加速或减速
+            Some(Action::SpeedUp) if self.speed_keys => self.change_speed(
+                self.speed
+                    .saturating_sub(SPEED_KEY_STEP)
+                    .clamp(MIN_SPEED, MAX_SPEED),
+            )?,
+            Some(Action::SpeedDown) if self.speed_keys => {
+                self.change_speed((self.speed + SPEED_KEY_STEP).clamp(MIN_SPEED, MAX_SPEED))?
+            }
+            // 显示或隐藏调试信息
+            Some(Action::Debug) => self.toggle_debug_overlay()?,
+            // 存档后继续,存不下来的模式里不存
+            Some(Action::Save) if self.unsaveable_mode().is_none() => self.save(SAVE_FILE)?,
+            _ => {}
+        }
+        // 暂停时只走一帧,方便一帧一帧地看
+        if self.paused && action == Some(Action::Step) {
+            //暂停时step()什么都不做,这一帧先取消暂停
+            self.paused = false;
+            let outcome = self.tick(next_tick);
+            self.paused = true;
+            if let Some(outcome) = outcome? {
+                return self.end_game(outcome).map(Some);
+            }
+            self.render_only_updated()?;
+            self.print_paused()?;
+        }
+        Ok(None)
     }
-    ///正常走
-    fn go(&mut self, x: usize, y: usize) {
-        let (h_x, h_y) = self.snack.head;
-        self.snack.bodys.push_front((h_x, h_y));
-        self.cells[h_x][h_y].set_type(CellType::SnackBody);
-        self.snack.head = (x, y);
-        self.cells[x][y].set_type(CellType::SnackHead);
-        //有可能没有body
-        if let Some((x, y)) = self.snack.bodys.pop_back() {
-            self.cells[x][y].set_type(CellType::Empty);
-        };
+    /// 一局结束,画出最后一帧,有蛇撞死的话播放死亡动画
+    fn end_game(&mut self, outcome: GameOutcome) -> Result<GameOutcome, GameError> {
+        self.bell(2)?;
+        self.render_only_updated()?;
+        let cells = self.dead_snake_cells(outcome);
+        if !cells.is_empty() && self.death_animation {
+            self.play_death_animation(cells)?;
+        }
+        Ok(outcome)
     }
-    ///碰撞检测
-    fn collision_detection(&mut self) -> (CellType, (usize, usize)) {
-        let (h_x, h_y) = self.snack.head;
-        let (n_x, n_y) = match self.snack.direction {
-            Direction::Left => (h_x as isize - 1, h_y as isize),
-            Direction::Right => (h_x as isize + 1, h_y as isize),
-            Direction::Up => (h_x as isize, h_y as isize - 1 as isize),
-            Direction::Down => (h_x as isize, h_y as isize + 1),
+    /// 这局撞死的蛇,每条从蛇尾到蛇头排好;撞墙、撞自己、被刀刃砍死,
+    /// 双人模式撞墙、撞到对方和迎头相撞死的都算,两条都死了先放玩家一的
+    fn dead_snake_cells(&self, outcome: GameOutcome) -> Vec<(usize, usize)> {
+        let tail_first = |snack: &Snack| {
+            snack
+                .bodys
+                .iter()
+                .rev()
+                .copied()
+                .chain(std::iter::once(snack.head))
+                .collect::<Vec<_>>()
         };
-        if n_x < 0 || n_y < 0 {
-            return (CellType::Wall, (0, 0));
+        match outcome {
+            GameOutcome::WallCollision
+            | GameOutcome::SelfCollision
+            | GameOutcome::BladeCollision => tail_first(&self.snack),
+            GameOutcome::TwoPlayerOver(_) => {
+                let mut cells = Vec::new();
+                if self.lives == 0 {
+                    cells.extend(tail_first(&self.snack));
+                }
+                if let Some(rival) = self.rival.as_ref().filter(|rival| !rival.alive) {
+                    cells.extend(tail_first(&rival.snack));
+                }
+                cells
+            }
+            //退出、时间到和各种赢法都没有蛇死
+            GameOutcome::Quit
+            | GameOutcome::TimeUp
+            | GameOutcome::Won
+            | GameOutcome::Victory
+            | GameOutcome::TargetReached(_)
+            | GameOutcome::LengthReached(_) => Vec::new(),
         }
-        (
-            self.cells[n_x as usize][n_y as usize].cell_type,
-            (n_x as usize, n_y as usize),
-        )
     }
-    ///随机生成食物
-    fn generage_food(&mut self) -> IOResult<()> {
-        let empty_cells = self
-            .cells
-            .iter()
-            .flat_map(|column| {
-                column.iter().filter_map(|c| {
-                    if c.cell_type == CellType::Empty {
-                        Some((c.x, c.y))
-                    } else {
-                        None
-                    }
-                })
-            })
-            .collect::<Vec<_>>();
-        if empty_cells.is_empty() {
-            self.writer.execute(LeaveAlternateScreen)?;
-            Err(Error::new(ErrorKind::Other, "没有足够的空间生成食物"))
-        } else {
-            let index = rand::thread_rng().gen_range(0..empty_cells.len());
-            let (x, y) = empty_cells[index];
-            self.cells[x][y].set_type(CellType::Food);
-            Ok(())
+    /// 死了的蛇按cells的顺序一节一节变红,蛇再长也在约一秒内播完
+    fn play_death_animation(&mut self, cells: Vec<(usize, usize)>) -> IOResult<()> {
+        let frame = (DEATH_ANIMATION / cells.len() as u32).min(DEATH_FRAME);
+        for (x, y) in cells {
+            self.set_cell_highlighted(x, y, true);
+            self.render_only_updated()?;
+            thread::sleep(frame);
         }
+        Ok(())
     }
-    ///吃
-    fn eat_food(&mut self, x: usize, y: usize) -> IOResult<()> {
-        self.generage_food()?;
-        let (h_x, h_y) = self.snack.head;
-        self.snack.bodys.push_front((h_x, h_y));
-        self.cells[h_x][h_y].set_type(CellType::SnackBody);
-        self.snack.head = (x, y);
-        self.cells[x][y].set_type(CellType::SnackHead);
-        self.score += 1;
+    /// 游戏循环中推进一帧,读取这一帧的输入,一局结束时返回结束原因
+    fn tick(&mut self, next_tick: &mut Instant) -> Result<Option<GameOutcome>, GameError> {
+        let input = if self.autopilot {
+            Some(self.ai_next_direction())
+        } else if let Some(source) = self.input_source.as_mut() {
+            source.next_direction()
+        } else {
+            //辅助模式这一帧没按键时朝最近的食物拐,反向操作时先反过来,转向时再反回去
+            self.next_queued_direction().or_else(|| {
+                self.assist
+                    .then(|| self.controlled(self.ai_next_direction()))
+            })
+        };
+        let rival_input = self.next_rival_direction();
+        let result = self.step_two(input, rival_input)?;
+        let outcome = match result {
+            StepResult::LostLife => {
+                self.bell(2)?;
+                None
+            }
+            StepResult::WallGrace => {
+                *next_tick += Duration::from_millis(WALL_GRACE_PAUSE);
+                None
+            }
+            _ => self.game_over_cause(result),
+        };
+        if outcome.is_none() {
+            self.update_edge_warning();
+        }
+        Ok(outcome)
+    }
+    /// 蛇头下一格是墙时把那面墙高亮,只是提醒,墙还是墙
+    fn update_edge_warning(&mut self) {
+        if !self.edge_warning {
+            return;
+        }
+        let ahead = self
+            .next_position(self.snack.direction)
+            .filter(|&(x, y)| self.cells[x][y].cell_type == CellType::Wall);
+        if let Some((x, y)) = std::mem::replace(&mut self.warned_wall, ahead) {
+            self.set_cell_highlighted(x, y, false);
+        }
+        if let Some((x, y)) = ahead {
+            self.set_cell_highlighted(x, y, true);
+        }
+    }
+    /// 推进一帧,不渲染也不等待,input为这一帧的转向,暂停时什么都不做
+    pub fn step(&mut self, input: Option<Direction>) -> Result<StepResult, GameError> {
+        if self.paused {
+            return Ok(StepResult::Paused);
+        }
+        //竞速模式从第一帧开始计时
+        if self.target_score.is_some() && self.run_start.is_none() {
+            self.run_start = Some(Instant::now());
+        }
+        if let Some(recording) = self.recording.as_mut() {
+            recording.push(input);
+        }
+        if let Some(dir) = input {
+            self.turn_around(dir);
+        }
+        let (o_x, o_y) = self.snack.head;
+        //上一帧宽限过的墙停止闪烁
+        let grace = self.grace_wall.take();
+        if let Some((x, y)) = grace {
+            self.set_cell_faded(x, y, false);
+        }
+        //推迟的生长到时间了,这一帧开始从尾巴长出来
+        while let Some(&(tick, growth)) = self.delayed_growth.front() {
+            if tick > self.ticks {
+                break;
+            }
+            self.pending_growth += growth;
+            self.delayed_growth.pop_front();
+        }
+        let invincible = self.invuln_frames > 0;
+        self.invuln_frames = self.invuln_frames.saturating_sub(1);
+        self.self_overlap = self.self_overlap.saturating_sub(1);
+        // 处理下一帧
+        let exit = self.snack.head;
+        let wrapped = self.crosses_edge();
+        let next = self.collision_detection();
+        self.log_frame(next)?;
+        let result = match next {
+            //复活后无敌时撞墙不死也不动,等玩家转向
+            (CellType::Wall | CellType::Portal, _) if invincible => StepResult::Blocked,
+            //宽限模式第一次撞墙不动,闪一下那面墙,连续两帧朝墙走才死
+            (CellType::Wall | CellType::Portal, (x, y)) if self.wall_grace && grace.is_none() => {
+                self.grace_wall = Some((x, y));
+                if self.cells[x][y].cell_type == CellType::Wall {
+                    self.set_cell_faded(x, y, true);
+                }
+                return Ok(StepResult::WallGrace);
+            }
+            //碰撞检测已经穿过了传送门,不会停在传送门上
+            (CellType::Wall | CellType::Portal, _) if self.wall_costs_life => {
+                return self.die(StepResult::HitWall)
+            }
+            (CellType::Wall | CellType::Portal, _) => {
+                self.lives = 0;
+                self.emit(GameEvent::Died(StepResult::HitWall));
+                return Ok(StepResult::HitWall);
+            }
+            (CellType::SnackHead, _) => StepResult::Moved,
+            //练习模式和复活后无敌时穿过自己的身体
+            (CellType::SnackBody, (x, y)) if !self.self_collision || invincible => {
+                //叠着的那节最多再过蛇长这么多帧就从蛇尾走掉了
+                if invincible {
+                    self.self_overlap = self.snake_len() as u64;
+                }
+                self.go(x, y);
+                StepResult::Moved
+            }
+            //蛇尾这一帧会空出来,可以跟着蛇尾走
+            (CellType::SnackBody, (x, y)) if self.is_leaving_tail(x, y) => {
+                self.go(x, y);
+                StepResult::Moved
+            }
+            (CellType::SnackBody, _) => return self.die(StepResult::HitSelf),
+            //大吃小:比对方长就是对方死
+            (CellType::Snack2Body, _) if self.eats_rival() => {
+                if let Some(rival) = self.rival.as_mut() {
+                    rival.alive = false;
+                }
+                return Ok(StepResult::RivalDied);
+            }
+            (CellType::Snack2Head | CellType::Snack2Body, _) => {
+                return self.die(StepResult::HitRival)
+            }
+            //吃完放不下新食物就是赢了
+            (CellType::Food, (x, y)) => {
+                if self.eat_food(x, y)? {
+                    StepResult::BoardFull
+                } else {
+                    StepResult::AteFood
+                }
+            }
+            (CellType::BonusFood, (x, y)) => {
+                self.eat_bonus(x, y);
+                StepResult::AteBonus
+            }
+            //刀刃把蛇从中间砍断,剩下的太短就结束
+            (CellType::Blade, (x, y)) => {
+                let kept = self.snack.bodys.len() / 2;
+                if kept + 1 < self.blade_min_length {
+                    return self.die(StepResult::HitBlade);
+                }
+                self.cut_by_blade(x, y, kept);
+                StepResult::Cut
+            }
+            (CellType::Poison, (x, y)) => {
+                self.eat_poison(x, y);
+                StepResult::AtePoison
+            }
+            (CellType::Runner, (x, y)) => {
+                self.eat_runner(x, y)?;
+                StepResult::AteBonus
+            }
+            (CellType::Empty, (x, y)) => {
+                self.go(x, y);
+                StepResult::Moved
+            }
+        };
+        //穿墙时蛇头一步跳到了对边,走传送门不算
+        let (n_x, n_y) = self.snack.head;
+        let (width, height) = (self.cells.len(), self.cells[0].len());
+        self.check_lap(o_x.abs_diff(n_x) == width - 1 || o_y.abs_diff(n_y) == height - 1);
+        self.ticks += 1;
+        self.stats.frames += 1;
+        self.update_wrap_flash();
+        if wrapped && self.wrap_cost > 0 {
+            self.score = self.score.saturating_sub(self.wrap_cost);
+            self.set_cell_highlighted(exit.0, exit.1, true);
+            self.wrap_flash = Some((exit, WRAP_FLASH_FRAMES));
+            self.print_score()?;
+        }
+        self.check_wall_hugging()?;
+        //连击窗口过了倍数回到1
+        if self.combo > 1
+            && self
+                .last_eat_tick
+                .is_some_and(|tick| self.ticks - tick > COMBO_WINDOW)
+        {
+            self.combo = 1;
+            self.print_score()?;
+        }
+        if self.first_food_delay > 0 && self.ticks == self.first_food_delay {
+            self.fill_initial_food();
+        }
+        self.move_runner();
+        self.check_shrink();
+        self.update_body_shades();
+        self.update_ghost();
+        //奖励食物过期消失,这一帧被吃掉的已经不在了
+        if let Some(((x, y), frames)) = self.bonus {
+            if frames == 0 {
+                self.set_cell(x, y, CellType::Empty);
+                self.bonus = None;
+            } else {
+                self.bonus = Some(((x, y), frames - 1));
+            }
+        }
+        self.update_food_blink();
+        self.update_tail();
+        self.update_move_hint();
+        //分值降到最低时食物变暗
+        if let Some(decay) = self.food_decay {
+            for i in 0..self.foods.len() {
+                let ((x, y), spawn_tick) = self.foods[i];
+                let faded = decay.value(self.ticks - spawn_tick) <= decay.floor;
+                self.set_cell_faded(x, y, faded);
+            }
+        }
+        self.rot_food()?;
+        //残影到时间消失,已经被占用的格子不用管
+        for i in 0..self.trail.len() {
+            self.trail[i].1 -= 1;
+            let ((x, y), frames) = self.trail[i];
+            if frames == 0 && self.cells[x][y].cell_type == CellType::Empty {
+                self.set_cell_faded(x, y, false);
+            }
+        }
+        self.trail.retain(|&(_, frames)| frames > 0);
+        //复活后闪烁几帧
+        if self.respawn_flash > 0 {
+            self.respawn_flash -= 1;
+            let faded = self.respawn_flash % 2 == 1;
+            self.set_snack_faded(faded);
+        }
+        //长到目标长度就赢了,毒果会让长度和分数对不上,所以每帧都看
+        if self
+            .target_length
+            .is_some_and(|target| self.snake_len() >= target)
+        {
+            return Ok(StepResult::LengthReached);
+        }
+        //竞速模式达到目标分数就结束
+        if matches!(result, StepResult::AteFood | StepResult::AteBonus)
+            && self.target_score.is_some_and(|target| self.score >= target)
+        {
+            return Ok(StepResult::TargetReached);
+        }
+        //关卡模式吃够分数换下一关
+        if matches!(result, StepResult::AteFood | StepResult::AteBonus) {
+            if let Some(result) = self.check_level_up()? {
+                return Ok(result);
+            }
+        }
+        Ok(result)
+    }
+    /// 最多推进n帧,每帧和游戏循环一样取一个按下的转向,不碰终端、不渲染也不等待,
+    /// 结束了提前返回原因,n帧走完还没结束返回None
+    pub fn advance(&mut self, n: u64) -> Result<Option<GameOutcome>, GameError> {
+        for _ in 0..n {
+            let input = self.next_queued_direction();
+            let rival_input = self.next_rival_direction();
+            let result = self.step_two(input, rival_input)?;
+            if let Some(cause) = self.game_over_cause(result) {
+                return Ok(Some(cause));
+            }
+        }
+        Ok(None)
+    }
+    /// 双人模式推进一帧,两条蛇先后移动,单人模式下等同于`step()`
+    pub fn step_two(
+        &mut self,
+        input: Option<Direction>,
+        rival_input: Option<Direction>,
+    ) -> Result<StepResult, GameError> {
+        if self.paused {
+            return Ok(StepResult::Paused);
+        }
+        //两个蛇头撞进同一格先按规则处理,再做各自的碰撞检查
+        if let Some(result) = self.resolve_head_on(input, rival_input)? {
+            return Ok(result);
+        }
+        let result = self.step(input)?;
+        let rival_died = self.step_rival(rival_input)?;
+        if rival_died && death_cause(result).is_none() && result != StepResult::HitRival {
+            Ok(StepResult::RivalDied)
+        } else {
+            Ok(result)
+        }
+    }
+    /// 这一帧之后游戏结束的原因,双人模式有一条蛇死了就分出胜负
+    fn game_over_cause(&self, result: StepResult) -> Option<GameOutcome> {
+        match (self.winner(), result) {
+            (Some(winner), _) => Some(GameOutcome::TwoPlayerOver(winner)),
+            (None, StepResult::BoardFull) => Some(GameOutcome::Won),
+            (None, StepResult::Won) => Some(GameOutcome::Victory),
+            (None, StepResult::TargetReached) => Some(GameOutcome::TargetReached(
+                self.speedrun_time().unwrap_or_default(),
+            )),
+            (None, StepResult::LengthReached) => Some(GameOutcome::LengthReached(self.snake_len())),
+            (None, _) => death_cause(result),
+        }
+    }
+    /// 按顺序每帧应用一个输入,死了或输入用完就停下,返回最后一帧的结果,不渲染也不等待
+    pub fn simulate<I: IntoIterator<Item = Option<Direction>>>(
+        &mut self,
+        inputs: I,
+    ) -> Result<StepResult, GameError> {
+        let mut result = StepResult::Moved;
+        for input in inputs {
+            result = self.step(input)?;
+            if self.game_over_cause(result).is_some() || result == StepResult::HitRival {
+                break;
+            }
+        }
+        Ok(result)
+    }
+    /// 最多推进n帧,每帧从inputs取一个转向,取完了就不转,不渲染也不等待,
+    /// 一局提前结束时返回结束原因,n帧走完还没结束返回None,方便属性测试和模糊测试
+    pub fn run_ticks<I: IntoIterator<Item = Option<Direction>>>(
+        &mut self,
+        n: usize,
+        inputs: I,
+    ) -> Result<Option<GameOutcome>, GameError> {
+        let mut inputs = inputs.into_iter();
+        for _ in 0..n {
+            let result = self.step(inputs.next().flatten())?;
+            if let Some(outcome) = self.game_over_cause(result) {
+                return Ok(Some(outcome));
+            }
+        }
+        Ok(None)
+    }
+    /// 死亡,还有命就复活
+    fn die(&mut self, cause: StepResult) -> Result<StepResult, GameError> {
+        self.emit(GameEvent::Died(cause));
+        if self.lives > 1 && self.rival.is_none() {
+            self.lives -= 1;
+            self.respawn()?;
+            Ok(StepResult::LostLife)
+        } else {
+            self.lives = 0;
+            Ok(cause)
+        }
+    }
+    /// 在起点复活,分数保留
+    fn respawn(&mut self) -> Result<(), GameError> {
+        let old = std::mem::replace(&mut self.snack, self.initial_snack.clone());
+        for (x, y) in std::iter::once(old.head).chain(old.bodys) {
+            self.set_cell(x, y, CellType::Empty);
+        }
+        //起点上的食物会被蛇盖住
+        let snack = self.snack.clone();
+        let cells = std::iter::once((snack.head, CellType::SnackHead))
+            .chain(snack.bodys.iter().map(|&pos| (pos, CellType::SnackBody)));
+        for ((x, y), t) in cells {
+            match self.cells[x][y].cell_type {
+                CellType::Food => self.remove_food((x, y)),
+                CellType::BonusFood => self.bonus = None,
+                CellType::Poison => self.poison = None,
+                CellType::Runner => self.runner = None,
+                _ => {}
+            }
+            self.set_cell(x, y, t);
+        }
+        self.fill_food();
+        self.input_queue.clear();
+        self.input_key = None;
+        self.tail_cell = None;
+        self.last_wrap = None;
+        //无敌的时候一直闪,闪烁停了就是无敌结束了
+        self.invuln_frames = self.mercy_frames;
+        self.respawn_flash = RESPAWN_FLASH_FRAMES.max(self.mercy_frames);
+        Ok(())
+    }
+    /// 整条蛇变暗或恢复
+    fn set_snack_faded(&mut self, faded: bool) {
+        let (x, y) = self.snack.head;
+        self.set_cell_faded(x, y, faded);
+        for i in 0..self.snack.bodys.len() {
+            let (x, y) = self.snack.bodys[i];
+            self.set_cell_faded(x, y, faded);
+        }
+    }
+    pub fn score(&self) -> usize {
+        self.score
+    }
+    /// 棋盘尺寸(宽, 高)
+    pub fn size(&self) -> (usize, usize) {
+        (self.cells.len(), self.cells[0].len())
+    }
+    /// 格子类型,越界时返回None
+    pub fn cell_type(&self, x: usize, y: usize) -> Option<CellType> {
+        self.cells.get(x)?.get(y).map(|cell| cell.cell_type)
+    }
+    /// 整个棋盘的格子类型,从上到下每行一个迭代器,行内从左到右,不复制棋盘;
+    /// 要留下来比较时`grid().map(|row| row.collect::<Vec<_>>()).collect::<Vec<_>>()`
+    pub fn grid(&self) -> impl Iterator<Item = impl Iterator<Item = CellType> + '_> + '_ {
+        let (_, height) = self.size();
+        (0..height).map(move |y| self.cells.iter().map(move |column| column[y].cell_type))
+    }
+    /// 蛇占的格子,先是蛇头,然后从前到后是蛇身
+    pub fn snake_cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        std::iter::once(self.snack.head).chain(self.snack.bodys.iter().copied())
+    }
+    /// 所有食物的位置,先是普通食物,然后是奖励食物和毒果,直接取记录的位置不扫描棋盘
+    pub fn food_positions(&self) -> Vec<(usize, usize)> {
+        self.foods
+            .iter()
+            .map(|&(pos, _)| pos)
+            .chain(self.bonus.map(|(pos, _)| pos))
+            .chain(self.poison)
+            .chain(self.runner)
+            .collect()
+    }
+    /// 在指定的空格子上放一个食物,下一帧渲染出来,方便布置测试和教学场景
+    pub fn place_food_at(&mut self, x: usize, y: usize) -> Result<(), GameError> {
+        let (width, height) = self.size();
+        if x >= width || y >= height {
+            return Err(GameError::CellOutOfBounds(x, y));
+        }
+        if self.cells[x][y].cell_type != CellType::Empty {
+            return Err(GameError::CellOccupied(x, y));
+        }
+        self.add_food(x, y);
+        Ok(())
+    }
+    /// 让蛇转向dir,下一帧`step()`时生效,掉头或和当前方向相同时不转并返回false
+    pub fn steer(&mut self, dir: Direction) -> bool {
+        self.turn_around(dir)
+    }
+    /// 蛇的长度,包括蛇头
+    pub fn snake_len(&self) -> usize {
+        self.snack.bodys.len() + 1
+    }
+    /// 暂停,和按空格暂停一样,暂停时`step()`和`run_ticks()`不推进
+    pub fn pause(&mut self) -> Result<(), GameError> {
+        self.set_paused(true)?;
+        Ok(())
+    }
+    /// 继续,和暂停时按空格一样
+    pub fn resume(&mut self) -> Result<(), GameError> {
+        self.set_paused(false)?;
+        Ok(())
+    }
+    /// 是否暂停中
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    fn set_paused(&mut self, paused: bool) -> IOResult<()> {
+        self.paused = paused;
+        self.focus_paused = false;
+        if self.in_alt_screen {
+            self.print_paused()?;
+        }
+        Ok(())
+    }
+    /// 当前每帧间隔毫秒数
+    pub fn speed(&self) -> u64 {
+        self.speed
+    }
+    /// 设置每帧间隔毫秒数,和+/-调速一样限制在30到300之间,游戏中调用会刷新分数栏
+    pub fn set_speed(&mut self, ms: u64) -> Result<(), GameError> {
+        self.change_speed(ms.clamp(MIN_SPEED, MAX_SPEED))?;
+        Ok(())
+    }
+    /// 速度变了才通知并刷新分数栏
+    fn change_speed(&mut self, speed: u64) -> IOResult<()> {
+        if speed != self.speed {
+            self.speed = speed;
+            self.emit(GameEvent::SpeedChanged(speed));
+            if self.in_alt_screen {
+                self.print_score()?;
+            }
+        }
+        Ok(())
+    }
+    /// 当前棋盘的纯文本,一格一个字符,用设置的字符集,每行之间换行,不带颜色,方便快照测试。
+    /// 默认字符集里墙和空地都是方块,快照里要分清的话用`Glyphs::ascii()`
+    pub fn render_to_string(&self) -> String {
+        let (width, height) = self.size();
+        let mut text = String::with_capacity((width + 1) * height);
+        for y in 0..height {
+            if y > 0 {
+                text.push('\n');
+            }
+            for x in 0..width {
+                let glyph = self.special_glyph(x, y);
+                text.push(glyph.unwrap_or_else(|| self.glyphs.get(self.cells[x][y].cell_type)));
+            }
+        }
+        text
+    }
+    /// 输出目标
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+    /// 会话统计
+    pub fn stats(&self) -> &GameStats {
+        &self.session
+    }
+    /// 本局统计
+    pub fn game_stats(&self) -> &Stats {
+        &self.stats
+    }
+    /// 逻辑加渲染耗时超过一帧时长的帧数
+    pub fn slow_frames(&self) -> usize {
+        self.slow_frames
+    }
+    /// 注册事件回调,吃食物、转向、死亡和变速时调用,替换之前注册的回调
+    pub fn on_event<F: FnMut(GameEvent) + 'static>(&mut self, callback: F) {
+        self.on_event = Some(Box::new(callback));
+    }
+    /// 用其他输入来源代替键盘,比如`ScriptedInput`,设置后不读取终端事件,
+    /// 也不进入原始模式、不显示开始菜单,一局结束直接返回
+    pub fn input_source<S: InputSource + 'static>(&mut self, source: S) {
+        self.input_source = Some(Box::new(source));
+    }
+    /// 用其他方式决定食物位置,比如`FirstEmpty`,只影响之后放的食物,
+    /// 要让开局的食物也按它放,在`build_with_writer`之后、开始游戏之前设置
+    pub fn food_spawner<S: FoodSpawner + 'static>(&mut self, spawner: S) {
+        self.food_spawner = Some(Box::new(spawner));
+    }
+    /// 竞速模式开始后经过的真实时间,暂停也计时,不是竞速模式或还没开始返回None
+    pub fn speedrun_time(&self) -> Option<Duration> {
+        self.run_start.map(|start| start.elapsed())
+    }
+    /// 清空会话统计
+    pub fn reset_session_stats(&mut self) {
+        self.session = GameStats::default();
+    }
+    /// 清空会话统计,`reset_session_stats`原来的名字,保留给已有的调用方
+    pub fn reset_stats(&mut self) {
+        self.reset_session_stats();
+    }
+    /// 底部展示分数,棋盘太窄时截掉放不下的部分
+    fn print_score(&mut self) -> IOResult<()> {
+        //(前面是否空4格, 内容)
+        let mut items = vec![
+            (false, "得分:".to_string().on_blue()),
+            (
+                false,
+                format!("{:^7}", self.score).green().on_white().bold(),
+            ),
+            (true, "Speed: ".to_string().on_blue()),
+            (false, format!("{:<3}", self.speed).red().on_white()),
+        ];
+        items.push((true, "连击:".to_string().on_blue()));
+        items.push((false, format!("x{}", self.combo).yellow().on_white()));
+        if self.wall_hug_every.is_some() {
+            items.push((true, "贴墙:".to_string().on_blue()));
+            items.push((
+                false,
+                format!("+{}", self.wall_hug_bonus).yellow().on_white(),
+            ));
+        }
+        if let Some(date) = self.daily.as_ref() {
+            items.push((true, "每日:".to_string().on_blue()));
+            items.push((false, date.clone().red().on_white()));
+        }
+        if self.invert_controls {
+            items.push((true, "反向操作".to_string().black().on_yellow()));
+        }
+        if self.initial_lives > 1 {
+            items.push((true, "命:".to_string().on_blue()));
+            items.push((false, self.lives.to_string().red().on_white()));
+        }
+        if let Some(score) = self.rival_score() {
+            items.push((true, "玩家二:".to_string().on_blue()));
+            items.push((false, format!("{:^7}", score).cyan().on_white().bold()));
+        }
+        if let Some((level, count)) = self.level() {
+            items.push((true, "关卡:".to_string().on_blue()));
+            items.push((false, format!("{}/{}", level, count).red().on_white()));
+        }
+        if let Some(target) = self.target_score {
+            let secs = self.speedrun_time().unwrap_or_default().as_secs_f64();
+            items.push((true, "目标:".to_string().on_blue()));
+            items.push((false, target.to_string().red().on_white()));
+            items.push((true, "用时:".to_string().on_blue()));
+            items.push((false, format!("{:>5.1}s", secs).red().on_white()));
+        }
+        if let Some(target) = self.target_length {
+            items.push((true, "长度:".to_string().on_blue()));
+            items.push((
+                false,
+                format!("{}/{}", self.snake_len(), target).red().on_white(),
+            ));
+        }
+        if let Some(secs) = self.remaining_secs() {
+            items.push((true, "剩余:".to_string().on_blue()));
+            items.push((false, format!("{:>3}s", secs).red().on_white()));
+        }
+        let tip = self.key_tip();
+        if !tip.is_empty() {
+            items.push((true, tip.grey().on_blue()));
+        }
+        if self.lap_bonus > 0 {
+            let message = format!("绕了一圈 +{}", self.lap_bonus);
+            //提示消失时用同样宽的空格盖住
+            items.push(if self.lap_message > 0 {
+                (true, message.black().on_yellow())
+            } else {
+                (true, " ".repeat(display_width(&message) as usize).stylize())
+            });
+        }
+        if let Some(renderer) = self.renderer.as_mut() {
+            let texts = items.iter().map(|(_, item)| item.content().as_str());
+            renderer.hud(&texts.collect::<Vec<_>>());
+            renderer.flush();
+            return Ok(());
+        }
+        let (width, height) = self.screen_size();
+        let row = self.hud_row(height) + self.offset.1;
+        //左边空4格,右边留出墙,放不下的截掉
+        let mut x = 4;
+        let mut room = (width as u16).saturating_sub(5);
+        let mut shown = Vec::with_capacity(items.len());
+        for (i, (gap, item)) in items.into_iter().enumerate() {
+            let gap = if gap { 4 } else { 0 };
+            if room <= gap {
+                break;
+            }
+            x += gap;
+            room -= gap;
+            let text = clip_to_width(item.content(), room);
+            let text_width = display_width(&text);
+            //和上次画的一样就不重画,标签一般只在第一次画
+            if self.hud.get(i) != Some(&(x, text.clone())) {
+                self.writer
+                    .queue(MoveTo(x + self.offset.0, row))?
+                    .queue(PrintStyledContent(paint(
+                        self.no_color,
+                        StyledContent::new(*item.style(), text.clone()),
+                    )))?;
+            }
+            shown.push((x, text));
+            x += text_width;
+            room -= text_width;
+        }
+        self.hud = shown;
+        self.writer.flush()?;
+        self.print_panel()
+    }
+    /// 询问退出的提示,后面是绑定的确认键和取消键
+    fn quit_prompt_text(&self) -> String {
+        let keys = [Action::Confirm, Action::Cancel]
+            .into_iter()
+            .filter_map(|action| self.key_bindings.first_key(action).map(key_label))
+            .collect::<Vec<_>>();
+        format!("退出? {}", keys.join("/"))
+    }
+    /// 分数栏的按键提示,每个操作用绑定的第一个键,没绑定键的操作不提示
+    fn key_tip(&self) -> String {
+        let key = |action| self.key_bindings.first_key(action).map(key_label);
+        let speed = match (
+            self.speed_keys,
+            key(Action::SpeedUp),
+            key(Action::SpeedDown),
+        ) {
+            (false, _, _) => None,
+            (true, Some(up), Some(down)) => Some(format!("{}/{}调速", up, down)),
+            (true, Some(up), None) => Some(format!("{}加速", up)),
+            (true, None, down) => down.map(|down| format!("{}减速", down)),
+        };
+        [
+            key(Action::Pause).map(|k| format!("{}暂停", k)),
+            speed,
+            key(Action::Save).map(|k| format!("{}存档", k)),
+            key(Action::Quit).map(|k| format!("按{}退出", k)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ")
+    }
+    /// 渲染的格子盖住了分数栏时,下次整行重画
+    fn check_hud_overdrawn(&mut self) {
+        let (_, height) = self.screen_size();
+        let block_h = self.zoom.map_or(1, |(_, block_h)| block_h);
+        let row = usize::from(self.hud_row(height));
+        if self.dirty.iter().any(|&(_, y)| y / block_h == row) {
+            self.hud.clear();
+        }
+    }
+    /// 分数栏在第几行,画在棋盘下面时是棋盘外的一行
+    fn hud_row(&self, height: usize) -> u16 {
+        if self.hud_below {
+            height as u16
+        } else {
+            height as u16 - 1
+        }
+    }
+    /// 限时模式剩余的秒数,向上取整
+    fn remaining_secs(&self) -> Option<u64> {
+        self.time_limit.map(|limit| {
+            let remaining = limit.saturating_sub(self.stats.play_time);
+            remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0)
+        })
+    }
+    /// 清屏后重画所有格子、标题和分数栏,终端内容可能被破坏之后调用
+    fn request_full_redraw(&mut self) -> IOResult<()> {
+        self.writer.queue(Clear(ClearType::All))?;
+        for cell in self.cells.iter_mut().flatten() {
+            cell.invalidate();
+        }
+        self.render_all()?;
+        self.print_title()?;
         self.print_score()?;
+        if self.paused {
+            self.print_paused()?;
+        }
+        Ok(())
+    }
+    /// 第一行中间的标题
+    fn print_title(&mut self) -> IOResult<()> {
+        self.writer
+            .queue(MoveTo(
+                (self.screen_size().0 as u16 - 6) / 2 + self.offset.0,
+                self.offset.1,
+            ))?
+            .queue(PrintStyledContent(paint(
+                self.no_color,
+                "贪吃蛇".green().on_black(),
+            )))?
+            .flush()?;
+        Ok(())
+    }
+    /// 标题旁显示或清除暂停提示
+    fn print_paused(&mut self) -> IOResult<()> {
+        let x = (self.screen_size().0 as u16 - 6) / 2 + 8;
+        //重画被提示盖住的格子,询问退出的提示最长
+        let quit_prompt = self.quit_prompt_text();
+        for i in x as usize..(x + display_width(&quit_prompt)) as usize {
+            self.invalidate_screen(i, 0);
+        }
+        self.render_only_updated()?;
+        let text = if self.quit_prompt.is_some() {
+            quit_prompt
+        } else if self.paused {
+            "暂停".to_string()
+        } else {
+            return Ok(());
+        };
+        self.writer
+            .queue(MoveTo(x + self.offset.0, self.offset.1))?
+            .queue(PrintStyledContent(paint(
+                self.no_color,
+                text.yellow().on_black(),
+            )))?
+            .flush()?;
+        Ok(())
+    }
+    ///同一方向连着穿两次墙,中间就是从一边走到了另一边,奖励lap_bonus分并提示几帧
+    fn check_lap(&mut self, wrapped: bool) {
+        if self.lap_bonus == 0 {
+            return;
+        }
+        self.lap_message = self.lap_message.saturating_sub(1);
+        if !wrapped {
+            return;
+        }
+        let dir = self.snack.moved;
+        if self.last_wrap.replace(dir) == Some(dir) {
+            self.score += self.lap_bonus;
+            self.lap_message = LAP_MESSAGE_FRAMES;
+        }
+    }
+    /// 从输入缓冲取出下一个转向,太久以前按的跳过
+    fn next_queued_direction(&mut self) -> Option<Direction> {
+        pop_direction(&mut self.input_queue, self.ticks, self.max_input_age_ticks)
+    }
+    /// 从第二条蛇的输入缓冲取下一个转向,单人模式返回None
+    fn next_rival_direction(&mut self) -> Option<Direction> {
+        let rival = self.rival.as_mut()?;
+        pop_direction(&mut rival.input_queue, self.ticks, self.max_input_age_ticks)
+    }
+    /// 显示或隐藏右上角的调试信息
+    fn toggle_debug_overlay(&mut self) -> IOResult<()> {
+        self.debug_overlay = !self.debug_overlay;
+        if !self.debug_overlay {
+            //重画被调试信息盖住的第一行
+            for x in 0..self.screen_size().0 {
+                self.invalidate_screen(x, 0);
+            }
+            self.render_only_updated()?;
+        }
+        Ok(())
+    }
+    /// 右上角显示帧率、这一帧的渲染耗时和更新的格子数
+    fn print_debug(
+        &mut self,
+        frame_time: Duration,
+        render_time: Duration,
+        dirty: usize,
+    ) -> IOResult<()> {
+        let fps = if frame_time.is_zero() {
+            0.0
+        } else {
+            1.0 / frame_time.as_secs_f64()
+        };
+        let text = format!(
+            "FPS {:>5.1} 渲染{:>5}us 更新{:>4}格",
+            fps,
+            render_time.as_micros(),
+            dirty
+        );
+        let width = self.screen_size().0;
+        let text = clip_to_width(&text, width.saturating_sub(2) as u16);
+        let x = width.saturating_sub(display_width(&text) as usize + 1) as u16;
+        self.writer
+            .queue(MoveTo(x + self.offset.0, self.offset.1))?
+            .queue(PrintStyledContent(paint(
+                self.no_color,
+                text.white().on_dark_grey(),
+            )))?
+            .flush()?;
+        Ok(())
+    }
+    /// 转向加入输入缓冲,和上一个相同或缓冲已满时丢弃
+    fn queue_direction(&mut self, dir: Direction) {
+        push_direction(&mut self.input_queue, dir, self.ticks);
+    }
+    /// 按键转向按`input_priority`加入输入缓冲,这一帧已经有转向时决定排队、替换还是丢掉
+    fn queue_key_turn(&mut self, dir: Direction, key: KeyCode) {
+        let this_tick = self
+            .input_queue
+            .back()
+            .is_some_and(|&(_, tick)| tick == self.ticks);
+        if let Some(earlier) = self.input_key.filter(|_| this_tick) {
+            if self.input_priority.keeps_earlier(earlier, key) {
+                return;
+            }
+            if self.input_priority != InputPriority::Buffered {
+                self.input_queue.pop_back();
+            }
+        }
+        self.input_key = Some(key);
+        self.queue_direction(dir);
+    }
+    ///设置转弯
+    fn turn_around(&mut self, dir: Direction) -> bool {
+        let dir = self.controlled(dir);
+        //以上一帧实际走的方向为准,一帧内多次转向也不能掉头
+        if dir != self.snack.moved.opposite() && dir != self.snack.direction {
+            self.snack.direction = dir;
+            self.stats.turns += 1;
+            self.emit(GameEvent::Turned(dir));
+            true
+        } else {
+            false
+        }
+    }
+    ///反向操作时把玩家按的方向反过来,自动驾驶不反
+    fn controlled(&self, dir: Direction) -> Direction {
+        if self.invert_controls && !self.autopilot {
+            dir.opposite()
+        } else {
+            dir
+        }
+    }
+    ///正常走
+    fn go(&mut self, x: usize, y: usize) {
+        self.grow(x, y);
+        if self.pending_growth > 0 {
+            self.pending_growth -= 1;
+            return;
+        }
+        //有可能没有body
+        if let Some((x, y)) = self.snack.bodys.pop_back() {
+            if self.clear_tail(x, y) && self.ghost_trail {
+                self.set_cell_faded(x, y, true);
+                self.trail.push(((x, y), TRAIL_FRAMES));
+            }
+        };
+    }
+    ///蛇头走上刀刃,刀刃没了,蛇身只留下靠近蛇头的kept节
+    fn cut_by_blade(&mut self, x: usize, y: usize, kept: usize) {
+        self.go(x, y);
+        while self.snack.bodys.len() > kept {
+            match self.snack.bodys.pop_back() {
+                Some((x, y)) => {
+                    self.clear_tail(x, y);
+                }
+                None => break,
+            }
+        }
+        //砍断后不再补长被砍掉的部分
+        self.pending_growth = 0;
+    }
+    ///去掉的蛇尾变回空格子,返回是否空出来了。蛇头跟着蛇尾走进来,
+    ///或者穿过自己时这一格还压着别的蛇身,就不清空
+    fn clear_tail(&mut self, x: usize, y: usize) -> bool {
+        let covered = self.snack.head == (x, y)
+            || ((!self.self_collision || self.invuln_frames > 0 || self.self_overlap > 0)
+                && self.snack.bodys.contains(&(x, y)));
+        if !covered {
+            self.set_cell(x, y, CellType::Empty);
+        }
+        !covered
+    }
+    ///(x, y)是不是这一帧会空出来的蛇尾,还要长长时蛇尾不动
+    fn is_leaving_tail(&self, x: usize, y: usize) -> bool {
+        self.tail_chasing && self.pending_growth == 0 && self.snack.bodys.back() == Some(&(x, y))
+    }
+    ///蛇头这一步会不会走出棋盘边缘,穿墙时就是从对边出来
+    fn crosses_edge(&self) -> bool {
+        let (d_x, d_y) = self.snack.direction.delta();
+        let (x, y) = self.snack.head;
+        let (width, height) = self.size();
+        x.checked_add_signed(d_x).is_none_or(|x| x >= width)
+            || y.checked_add_signed(d_y).is_none_or(|y| y >= height)
+    }
+    /// 蛇头上下左右有墙时记一帧,攒够了换成1分
+    fn check_wall_hugging(&mut self) -> IOResult<()> {
+        let Some(every) = self.wall_hug_every else {
+            return Ok(());
+        };
+        let (x, y) = self.snack.head;
+        let (width, height) = self.size();
+        let near_wall = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+            .iter()
+            .any(
+                |&(d_x, d_y)| match (x.checked_add_signed(d_x), y.checked_add_signed(d_y)) {
+                    (Some(x), Some(y)) if x < width && y < height => {
+                        self.cells[x][y].cell_type == CellType::Wall
+                    }
+                    _ => false,
+                },
+            );
+        if !near_wall {
+            return Ok(());
+        }
+        self.wall_hug_frames += 1;
+        if self.wall_hug_frames >= every {
+            self.wall_hug_frames = 0;
+            self.wall_hug_bonus += 1;
+            self.score += 1;
+            self.print_score()?;
+        }
+        Ok(())
+    }
+    ///穿墙扣分时闪一下出去的那一格,过几帧恢复
+    fn update_wrap_flash(&mut self) {
+        if let Some(((x, y), frames)) = self.wrap_flash {
+            if frames == 0 {
+                self.set_cell_highlighted(x, y, false);
+                self.wrap_flash = None;
+            } else {
+                self.wrap_flash = Some(((x, y), frames - 1));
+            }
+        }
+    }
+    ///碰撞检测
+    fn collision_detection(&mut self) -> (CellType, (usize, usize)) {
+        match self.next_position(self.snack.direction) {
+            Some((n_x, n_y)) => (self.cells[n_x][n_y].cell_type, (n_x, n_y)),
+            None => (CellType::Wall, (0, 0)),
+        }
+    }
+    ///蛇头朝dir走一步到达的位置,出了棋盘返回None
+    fn next_position(&self, dir: Direction) -> Option<(usize, usize)> {
+        self.destination(self.snack.head, dir)
+    }
+    ///从head朝dir走一步最终到达的位置,进了传送门就从配对的传送门前方出来,
+    ///出口前方还是传送门时当作撞墙,不会连续传送
+    fn destination(&self, head: (usize, usize), dir: Direction) -> Option<(usize, usize)> {
+        let (x, y) = self.next_position_from(head, dir)?;
+        if self.cells[x][y].cell_type != CellType::Portal {
+            return Some((x, y));
+        }
+        let exit = self.portals.iter().find_map(|&(a, b)| {
+            if a == (x, y) {
+                Some(b)
+            } else if b == (x, y) {
+                Some(a)
+            } else {
+                None
+            }
+        })?;
+        let (x, y) = self.next_position_from(exit, dir)?;
+        (self.cells[x][y].cell_type != CellType::Portal).then_some((x, y))
+    }
+    ///从head朝dir走一步到达的位置,出了棋盘返回None
+    fn next_position_from(
+        &self,
+        (h_x, h_y): (usize, usize),
+        dir: Direction,
+    ) -> Option<(usize, usize)> {
+        let (d_x, d_y) = dir.delta();
+        let (n_x, n_y) = (h_x as isize + d_x, h_y as isize + d_y);
+        let width = self.cells.len() as isize;
+        let height = self.cells[0].len() as isize;
+        let (n_x, n_y) = self.border.wrap_position((n_x, n_y), (width, height));
+        //地图边上没有墙时也不能越界
+        if n_x < 0 || n_y < 0 || n_x >= width || n_y >= height {
+            return None;
+        }
+        Some((n_x as usize, n_y as usize))
+    }
+    /// 自动驾驶:在不撞墙不撞自己的方向里选离最近的食物最近的,都不安全就保持原方向
+    pub fn ai_next_direction(&self) -> Direction {
+        let current = self.snack.direction;
+        let targets = || {
+            self.foods
+                .iter()
+                .map(|&(pos, _)| pos)
+                .chain(self.bonus.map(|(pos, _)| pos))
+                .chain(self.runner)
+        };
+        [
+            current,
+            Direction::Left,
+            Direction::Right,
+            Direction::Up,
+            Direction::Down,
+        ]
+        .into_iter()
+        .filter(|&dir| dir != self.snack.moved.opposite())
+        .filter_map(|dir| {
+            let (x, y) = self.next_position(dir)?;
+            match self.cells[x][y].cell_type {
+                CellType::Empty | CellType::Food | CellType::BonusFood | CellType::Runner => {
+                    let distance = targets()
+                        .map(|(t_x, t_y)| x.abs_diff(t_x) + y.abs_diff(t_y))
+                        .min()
+                        .unwrap_or(0);
+                    Some((distance, dir))
+                }
+                _ => None,
+            }
+        })
+        .min_by_key(|&(distance, _)| distance)
+        .map_or(current, |(_, dir)| dir)
+    }
+    /// 只在快过期时闪烁的模式下,奖励食物和会烂的食物剩下的帧数不多时标记出来
+    fn update_food_blink(&mut self) {
+        let Some(warning) = self.blink_warning else {
+            return;
+        };
+        if let Some(((x, y), frames)) = self.bonus {
+            if self.cells[x][y].set_expiring(frames <= warning) {
+                self.dirty.push((x, y));
+            }
+        }
+        if let Some((lifetime, _)) = self.food_lifetime {
+            for i in 0..self.foods.len() {
+                let ((x, y), spawn_tick) = self.foods[i];
+                let left = lifetime.saturating_sub(self.ticks - spawn_tick);
+                if self.cells[x][y].set_expiring(left <= warning) {
+                    self.dirty.push((x, y));
+                }
+            }
+        }
+    }
+    /// 单独显示蛇尾时把标记挪到现在的最后一节
+    fn update_tail(&mut self) {
+        if !self.distinct_tail {
+            return;
+        }
+        let tail = self.snack.bodys.back().copied();
+        if let Some((x, y)) = std::mem::replace(&mut self.tail_cell, tail) {
+            if Some((x, y)) != tail {
+                self.set_cell_tail(x, y, false);
+            }
+        }
+        if let Some((x, y)) = tail {
+            self.set_cell_tail(x, y, true);
+        }
+    }
+    /// 教学模式下把提示挪到自动驾驶建议走的下一格,只是显示,不替玩家转向
+    fn update_move_hint(&mut self) {
+        if !self.move_hints {
+            return;
+        }
+        let hint = self
+            .next_position(self.ai_next_direction())
+            .filter(|&(x, y)| self.cells[x][y].cell_type == CellType::Empty);
+        if let Some((x, y)) = std::mem::replace(&mut self.move_hint, hint) {
+            if self.cells[x][y].cell_type == CellType::Empty {
+                self.set_cell_faded(x, y, false);
+            }
+        }
+        if let Some((x, y)) = hint {
+            self.set_cell_faded(x, y, true);
+        }
+    }
+    ///清空cells再放进所有空格子,按行从上到下、行内从左到右排好,重用cells的内存。
+    ///随机放置时按下标选格子,这个顺序变了固定种子的食物序列也会变,所以不要跟着棋盘的存储方式改
+    pub(crate) fn collect_empty_cells(&self, cells: &mut Vec<(usize, usize)>) {
+        let (width, height) = self.size();
+        cells.clear();
+        cells.extend(
+            (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .filter(|&(x, y)| self.cells[x][y].cell_type == CellType::Empty),
+        );
+    }
+    ///随机选一个空格子,没有空位返回None,用`empty_scratch`不分配内存
+    pub(crate) fn random_empty_cell(&mut self) -> Option<(usize, usize)> {
+        let mut empty_cells = std::mem::take(&mut self.empty_scratch);
+        self.collect_empty_cells(&mut empty_cells);
+        let picked = (!empty_cells.is_empty())
+            .then(|| empty_cells[self.rng.gen_range(0..empty_cells.len())]);
+        self.empty_scratch = empty_cells;
+        picked
+    }
+    ///补充食物到设定的数量,放不下时有几个算几个,一个都放不下说明棋盘满了,返回false
+    fn fill_food(&mut self) -> bool {
+        self.fill_food_to(self.food_count)
+    }
+    ///放开局的食物,放不下时有几个算几个,一个都放不下返回false
+    fn fill_initial_food(&mut self) -> bool {
+        self.fill_food_to(self.initial_food.max(self.food_count))
+    }
+    fn fill_food_to(&mut self, count: usize) -> bool {
+        while self.foods.len() < count {
+            if !self.generage_food() {
+                break;
+            }
+        }
+        !self.foods.is_empty()
+    }
+    ///随机生成一个食物,没有空位时返回false
+    fn generage_food(&mut self) -> bool {
+        let mut empty_cells = std::mem::take(&mut self.empty_scratch);
+        self.collect_empty_cells(&mut empty_cells);
+        //离墙太近的格子不放食物,没有别的地方再放宽
+        let clearance = self.food_wall_clearance;
+        if clearance > 0
+            && empty_cells
+                .iter()
+                .any(|&(x, y)| self.clear_of_walls(x, y, clearance))
+        {
+            empty_cells.retain(|&(x, y)| self.clear_of_walls(x, y, clearance));
+        }
+        //蛇头正前方几格不放食物,来不及反应,没有别的地方再放宽
+        if self.food_head_clearance > 0 {
+            let clearance = self.food_head_clearance;
+            let ahead = |pos: (usize, usize)| self.cells_ahead(clearance).any(|p| p == pos);
+            if empty_cells.iter().any(|&pos| !ahead(pos)) {
+                empty_cells.retain(|&pos| !ahead(pos));
+            }
+        }
+        let picked = if empty_cells.is_empty() {
+            None
+        } else {
+            Some(match self.food_spawner.as_mut() {
+                Some(spawner) => spawner.place(&empty_cells),
+                None => empty_cells[self.rng.gen_range(0..empty_cells.len())],
+            })
+        };
+        self.empty_scratch = empty_cells;
+        let Some((x, y)) = picked else {
+            return false;
+        };
+        self.add_food(x, y);
+        true
+    }
+    ///放太久的食物烂掉,换个地方重新放,这一帧被吃掉的已经不在了
+    fn rot_food(&mut self) -> IOResult<()> {
+        let Some((lifetime, penalty)) = self.food_lifetime else {
+            return Ok(());
+        };
+        //烂掉的挪到后面,没烂的保持原来的顺序
+        let mut fresh = 0;
+        for i in 0..self.foods.len() {
+            if self.ticks - self.foods[i].1 < lifetime {
+                self.foods.swap(fresh, i);
+                fresh += 1;
+            }
+        }
+        let rotten = self.foods.len() - fresh;
+        if rotten == 0 {
+            return Ok(());
+        }
+        //先放新的再清掉烂的,烂的还占着格子,新食物不会原地长出来
+        self.fill_food_to(rotten + fresh.max(self.food_count));
+        for i in fresh..fresh + rotten {
+            let ((x, y), _) = self.foods[i];
+            self.food_points.remove(&(x, y));
+            self.food_distances.remove(&(x, y));
+            self.set_cell(x, y, CellType::Empty);
+        }
+        self.foods.drain(fresh..fresh + rotten);
+        if penalty > 0 {
+            self.score = self.score.saturating_sub(penalty * rotten);
+            self.print_score()?;
+        }
         Ok(())
     }
+    ///在(x, y)放一个食物,开启随机分值时定下它的分值
+    fn add_food(&mut self, x: usize, y: usize) {
+        self.set_cell(x, y, CellType::Food);
+        let (h_x, h_y) = self.snack.head;
+        self.food_distances
+            .insert((x, y), x.abs_diff(h_x) + y.abs_diff(h_y));
+        self.foods.push(((x, y), self.ticks));
+        if let Some((min, max)) = self.food_values {
+            let value = self.rng.gen_range(min..=max);
+            self.food_points.insert((x, y), value);
+        }
+    }
+    ///蛇头沿当前方向往前distance格,出了棋盘就停
+    pub(crate) fn cells_ahead(&self, distance: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let direction = self.snack.direction;
+        std::iter::successors(
+            self.next_position_from(self.snack.head, direction),
+            move |&pos| self.next_position_from(pos, direction),
+        )
+        .take(distance)
+    }
+    ///(x, y)周围distance格内没有墙
+    fn clear_of_walls(&self, x: usize, y: usize, distance: usize) -> bool {
+        let x_range = x.saturating_sub(distance)..=(x + distance).min(self.cells.len() - 1);
+        let y_range = y.saturating_sub(distance)..=(y + distance).min(self.cells[0].len() - 1);
+        x_range.into_iter().all(|i| {
+            y_range
+                .clone()
+                .all(|j| self.cells[i][j].cell_type != CellType::Wall)
+        })
+    }
+    ///吃食物,吃完放不下新食物时返回true
+    fn eat_food(&mut self, x: usize, y: usize) -> Result<bool, GameError> {
+        let value = self.food_value((x, y));
+        let bonus = self.distance_bonus((x, y));
+        self.remove_food((x, y));
+        //先移动蛇头再放新食物,按移动后的棋盘找空位
+        if self.growth_delay > 0 {
+            //这一帧照常移动,过几帧再从尾巴长出来
+            self.go(x, y);
+            self.delayed_growth
+                .push_back((self.ticks + self.growth_delay, self.growth_per_food));
+        } else {
+            self.grow(x, y);
+            //吃的这一帧长一节,剩下的在之后几帧从尾巴长出来
+            self.pending_growth += self.growth_per_food - 1;
+        }
+        let has_food = self.fill_food();
+        //窗口内连续吃到食物倍数加一,超过窗口从1开始
+        self.combo = match self.last_eat_tick {
+            Some(tick) if self.ticks - tick <= COMBO_WINDOW => (self.combo + 1).min(MAX_COMBO),
+            _ => 1,
+        };
+        self.last_eat_tick = Some(self.ticks);
+        self.score += value * self.combo + bonus;
+        self.check_hazards();
+        self.stats.food_eaten += 1;
+        self.session.food_eaten += 1;
+        self.emit(GameEvent::FoodEaten { score: self.score });
+        self.bell(1)?;
+        //越吃越快,设置了加速曲线就按分数算
+        let speed = match self.speed_curve {
+            Some(_) => self.speed_for_score(self.score),
+            None => self.speed.saturating_sub(SPEED_STEP).max(MIN_SPEED),
+        };
+        if speed != self.speed {
+            self.speed = speed;
+            self.emit(GameEvent::SpeedChanged(speed));
+        }
+        if self.bonus.is_none() && self.rng.gen_bool(self.bonus_chance) {
+            self.spawn_bonus();
+        }
+        if self.poison.is_none() && self.rng.gen_bool(POISON_CHANCE) {
+            self.spawn_poison();
+        }
+        self.spawn_runner();
+        Ok(!has_food)
+    }
+    /// 按加速曲线score分时的帧间隔,没有设置曲线时就是当前速度
+    fn speed_for_score(&self, score: usize) -> u64 {
+        self.speed_curve
+            .map_or(self.speed, |curve| curve.speed(self.initial_speed, score))
+    }
+    ///吃奖励食物
+    fn eat_bonus(&mut self, x: usize, y: usize) {
+        self.bonus = None;
+        self.grow(x, y);
+        self.score += BONUS_SCORE;
+        self.stats.food_eaten += 1;
+        self.session.food_eaten += 1;
+        self.emit(GameEvent::FoodEaten { score: self.score });
+    }
+    ///吃毒药,蛇尾缩短几节并扣分,最短只剩蛇头
+    fn eat_poison(&mut self, x: usize, y: usize) {
+        self.poison = None;
+        self.go(x, y);
+        for _ in 0..POISON_SEGMENTS {
+            match self.snack.bodys.pop_back() {
+                Some((x, y)) => {
+                    self.clear_tail(x, y);
+                }
+                None => break,
+            }
+        }
+        self.score = self.score.saturating_sub(POISON_PENALTY);
+    }
+    ///随机放一个毒药,没有空位就算了
+    fn spawn_poison(&mut self) {
+        if let Some((x, y)) = self.random_empty_cell() {
+            self.set_cell(x, y, CellType::Poison);
+            self.poison = Some((x, y));
+        }
+    }
+    ///随机放一个奖励食物,没有空位就算了
+    fn spawn_bonus(&mut self) {
+        if let Some((x, y)) = self.random_empty_cell() {
+            self.set_cell(x, y, CellType::BonusFood);
+            self.bonus = Some(((x, y), BONUS_FRAMES));
+        }
+    }
+    ///蛇头前进到(x, y),尾巴不动
+    fn grow(&mut self, x: usize, y: usize) {
+        let (h_x, h_y) = self.snack.head;
+        self.snack.bodys.push_front((h_x, h_y));
+        self.set_cell(h_x, h_y, CellType::SnackBody);
+        self.snack.head = (x, y);
+        self.snack.moved = self.snack.direction;
+        self.set_cell(x, y, CellType::SnackHead);
+    }
+    ///吃pos上的食物时按生成时的距离给的额外奖励,场上只有一个食物时没得比,不奖励
+    fn distance_bonus(&self, pos: (usize, usize)) -> usize {
+        let (Some(policy), Some(&distance)) =
+            (self.multi_food_bonus, self.food_distances.get(&pos))
+        else {
+            return 0;
+        };
+        let mut others = self
+            .food_distances
+            .iter()
+            .filter(|&(&p, _)| p != pos)
+            .map(|(_, &d)| d)
+            .peekable();
+        if others.peek().is_none() {
+            return 0;
+        }
+        match policy {
+            MultiFoodBonus::Closest(bonus) if others.all(|d| distance <= d) => bonus,
+            MultiFoodBonus::Farthest(bonus) if others.all(|d| distance >= d) => bonus,
+            _ => 0,
+        }
+    }
+
+    ///pos上食物的分值
+    fn food_value(&self, pos: (usize, usize)) -> usize {
+        let spawn_tick = self.foods.iter().find(|&&(p, _)| p == pos);
+        match (self.food_decay, spawn_tick) {
+            (Some(decay), Some(&(_, spawn_tick))) => decay.value(self.ticks - spawn_tick),
+            _ => self.food_points.get(&pos).copied().unwrap_or(1),
+        }
+    }
+    /// 去掉(x, y)上的食物和它的分值
+    pub(crate) fn remove_food(&mut self, pos: (usize, usize)) {
+        self.foods.retain(|&(p, _)| p != pos);
+        self.food_points.remove(&pos);
+        self.food_distances.remove(&pos);
+    }
 
+    ///开启声音时响铃times次
+    fn bell(&mut self, times: usize) -> IOResult<()> {
+        if !self.sound {
+            return Ok(());
+        }
+        for i in 0..times {
+            if i > 0 {
+                //连着响终端会合成一声
+                thread::sleep(Duration::from_millis(150));
+            }
+            self.writer.write_all(b"\x07")?;
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+    ///通知事件回调
+    fn emit(&mut self, event: GameEvent) {
+        if let Some(callback) = self.on_event.as_mut() {
+            callback(event);
+        }
+    }
+    ///设置格子类型并加入待渲染列表
+    fn set_cell(&mut self, x: usize, y: usize, t: CellType) {
+        if self.cells[x][y].set_type(t) {
+            self.dirty.push((x, y));
+        }
+    }
+    fn set_cell_faded(&mut self, x: usize, y: usize, faded: bool) {
+        if self.cells[x][y].set_faded(faded) {
+            self.dirty.push((x, y));
+        }
+    }
+    fn set_cell_tail(&mut self, x: usize, y: usize, tail: bool) {
+        if self.cells[x][y].set_tail(tail) {
+            self.dirty.push((x, y));
+        }
+    }
+    /// 蛇身每一节按离蛇头的远近分成几档深浅
+    fn update_body_shades(&mut self) {
+        if !self.body_gradient {
+            return;
+        }
+        let len = self.snack.bodys.len();
+        for i in 0..len {
+            let (x, y) = self.snack.bodys[i];
+            let shade = (i * BODY_SHADES / len) as u8;
+            if self.cells[x][y].set_shade(shade) {
+                self.dirty.push((x, y));
+            }
+        }
+    }
+    fn set_cell_highlighted(&mut self, x: usize, y: usize, highlighted: bool) {
+        if self.cells[x][y].set_highlighted(highlighted) {
+            self.dirty.push((x, y));
+        }
+    }
+    ///下次渲染时强制重画这个格子
+    fn invalidate_cell(&mut self, x: usize, y: usize) {
+        if self.cells[x][y].invalidate() {
+            self.dirty.push((x, y));
+        }
+    }
+    /// 这次渲染食物怎么闪烁
+    fn food_blink(&self) -> FoodBlink {
+        if !self.blink_food {
+            FoodBlink::Off
+        } else if self.blink_warning.is_some() {
+            FoodBlink::NearExpiry
+        } else {
+            FoodBlink::Always
+        }
+    }
     ///渲染全部格子
     fn render_all(&mut self) -> IOResult<()> {
-        for c in self.cells.iter_mut().flat_map(|c| c) {
-            c.render(&mut self.writer)?;
+        self.hud.clear();
+        let (width, height) = self.size();
+        let cells = (0..width).flat_map(|x| (0..height).map(move |y| (x, y)));
+        if self.draw_with_renderer(cells) {
+            self.dirty.clear();
+            return Ok(());
+        }
+        if self.zoom.is_some() {
+            self.dirty.clear();
+            let (width, height) = self.size();
+            let cells = (0..width).flat_map(|x| (0..height).map(move |y| (x, y)));
+            return self.render_blocks(cells.collect::<Vec<_>>());
         }
+        let (offset, look) = (self.offset, self.look());
+        let (width, height) = self.size();
+        for x in 0..width {
+            for y in 0..height {
+                let glyph = self.special_glyph(x, y);
+                self.cells[x][y].render(&mut self.writer, offset, &look, glyph)?;
+            }
+        }
+        self.dirty.clear();
         self.writer.flush()?;
         Ok(())
     }
+    /// 渲染格子用的外观设置
+    fn look(&self) -> Look {
+        Look {
+            theme: self.theme,
+            glyphs: self.glyphs,
+            blink: self.food_blink(),
+            background: self.background,
+            cell_width: self.cell_width,
+            body_gradient: self.body_gradient,
+            no_color: self.no_color,
+        }
+    }
+    /// 不按格子类型显示的字符:蛇头显示朝向,有分值的食物显示分数
+    fn special_glyph(&self, x: usize, y: usize) -> Option<char> {
+        match self.cells[x][y].cell_type {
+            //标尺:上边的墙上是列号,左边的墙上是行号,逢十显示十位
+            CellType::Wall if self.ruler && (x == 0 || y == 0) && (x, y) != (0, 0) => {
+                let i = if y == 0 { x } else { y };
+                let digit = if i % 10 == 0 { i / 10 % 10 } else { i % 10 };
+                char::from_digit(digit as u32, 10)
+            }
+            CellType::SnackHead if (x, y) == self.snack.head => {
+                Some(self.glyphs.head(self.snack.moved))
+            }
+            CellType::Food => self
+                .food_points
+                .get(&(x, y))
+                .and_then(|&value| char::from_digit(value as u32, 10)),
+            _ => None,
+        }
+    }
     /// 只渲染需要更新的格子
     fn render_only_updated(&mut self) -> IOResult<()> {
-        self.cells.iter_mut().for_each(|columns| {
-            columns
-                .iter_mut()
-                .filter(|cell| cell.changed_flag)
-                .for_each(|cell| {
-                    cell.render(&mut self.writer).unwrap();
-                })
-        });
+        let mut dirty = std::mem::take(&mut self.dirty);
+        if !self.draw_with_renderer(dirty.iter().copied()) {
+            self.dirty = dirty;
+            self.check_hud_overdrawn();
+            if self.zoom.is_some() {
+                let dirty = std::mem::take(&mut self.dirty);
+                return self.render_blocks(dirty);
+            }
+            dirty = std::mem::take(&mut self.dirty);
+            self.draw_dirty(&dirty)?;
+        }
+        //清空后放回去,下一帧接着用,不用每帧重新分配
+        dirty.clear();
+        self.dirty = dirty;
+        Ok(())
+    }
+    /// 用crossterm画出这些格子
+    fn draw_dirty(&mut self, dirty: &[(usize, usize)]) -> IOResult<()> {
+        match dirty_region(dirty) {
+            //改动挤在一块时每行只移动一次光标,整块重画
+            Some((xs, ys))
+                if dirty.len() > 1
+                    && dirty.len() * REGION_DENSITY.1 >= xs.len() * ys.len() * REGION_DENSITY.0 =>
+            {
+                self.draw_region(xs, ys)?
+            }
+            _ => self.draw_cells(dirty)?,
+        }
         self.writer.flush()?;
         Ok(())
     }
+    /// 整块重画,每行只移动一次光标
+    fn draw_region(&mut self, xs: Range<usize>, ys: Range<usize>) -> IOResult<()> {
+        let (offset, look) = (self.offset, self.look());
+        for y in ys {
+            self.writer.queue(MoveTo(
+                xs.start as u16 * self.cell_width + offset.0,
+                y as u16 + offset.1,
+            ))?;
+            for x in xs.clone() {
+                let glyph = self.special_glyph(x, y);
+                self.cells[x][y].print(&mut self.writer, &look, glyph)?;
+            }
+        }
+        Ok(())
+    }
+    /// 逐格移动光标,只画变了的格子
+    fn draw_cells(&mut self, dirty: &[(usize, usize)]) -> IOResult<()> {
+        let (offset, look) = (self.offset, self.look());
+        for &(x, y) in dirty {
+            let glyph = self.special_glyph(x, y);
+            self.cells[x][y].render(&mut self.writer, offset, &look, glyph)?;
+        }
+        Ok(())
+    }
+}
+/// 改动的格子的范围(列, 行),没有改动时返回None
+fn dirty_region(dirty: &[(usize, usize)]) -> Option<(Range<usize>, Range<usize>)> {
+    let &(x, y) = dirty.first()?;
+    let (mut min, mut max) = ((x, y), (x, y));
+    for &(x, y) in dirty {
+        min = (min.0.min(x), min.1.min(y));
+        max = (max.0.max(x), max.1.max(y));
+    }
+    Some((min.0..max.0 + 1, min.1..max.1 + 1))
+}
+/// 致命的一帧对应的死因,没死返回None
+fn death_cause(result: StepResult) -> Option<GameOutcome> {
+    match result {
+        StepResult::HitWall => Some(GameOutcome::WallCollision),
+        StepResult::HitSelf => Some(GameOutcome::SelfCollision),
+        StepResult::HitBlade => Some(GameOutcome::BladeCollision),
+        _ => None,
+    }
+}
+/// 转向加入输入缓冲,和上一个相同或缓冲已满时丢弃
+fn push_direction(queue: &mut VecDeque<(Direction, u64)>, dir: Direction, tick: u64) {
+    if queue.len() < INPUT_QUEUE_LEN && queue.back().map(|&(d, _)| d) != Some(dir) {
+        queue.push_back((dir, tick));
+    }
 }
-impl Drop for Game {
-    fn drop(&mut self) {
-        disable_raw_mode().unwrap();
+/// 取出第一个没超过max_age帧的转向,超时的直接丢掉
+fn pop_direction(
+    queue: &mut VecDeque<(Direction, u64)>,
+    now: u64,
+    max_age: Option<u64>,
+) -> Option<Direction> {
+    while let Some((dir, tick)) = queue.pop_front() {
+        if max_age.is_none_or(|max_age| now.saturating_sub(tick) <= max_age) {
+            return Some(dir);
+        }
+    }
+    None
+}
+/// 文字在终端中占的列数,中文占两格宽
+fn display_width(text: &str) -> u16 {
+    text.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum()
+}
+/// 检查棋盘放得下开局的蛇和至少一个食物,所有创建游戏的方式都会检查。
+/// snack为None时是默认的开局,棋盘还要放得下分数栏,不能小于`MIN_WIDTH`x`MIN_HEIGHT`
+fn validate_dimensions(
+    width: usize,
+    height: usize,
+    snack: Option<&Snack>,
+) -> Result<(), GameError> {
+    if let Some(snack) = snack {
+        //设定的蛇要在棋盘以内,蛇以外还要留出放食物的格子
+        let (min_width, min_height) = std::iter::once(snack.head)
+            .chain(snack.bodys.iter().copied())
+            .fold((1, 1), |(w, h), (x, y)| (w.max(x + 1), h.max(y + 1)));
+        if width < min_width || height < min_height || width * height <= snack.bodys.len() + 1 {
+            return Err(GameError::BoardTooSmall {
+                width,
+                height,
+                min_width,
+                min_height,
+            });
+        }
+        return Ok(());
+    }
+    let too_small = GameError::BoardTooSmall {
+        width,
+        height,
+        min_width: MIN_WIDTH,
+        min_height: MIN_HEIGHT,
+    };
+    if width < MIN_WIDTH || height < MIN_HEIGHT {
+        return Err(too_small);
+    }
+    //默认的蛇要在边界墙以内,蛇以外还要留出放食物的空格
+    let snack = Snack::new();
+    let fits = std::iter::once(snack.head)
+        .chain(snack.bodys.iter().copied())
+        .all(|(x, y)| x > 0 && y > 0 && x < width - 1 && y < height - 1);
+    if !fits || (width - 2) * (height - 2) <= snack.bodys.len() + 1 {
+        return Err(too_small);
+    }
+    Ok(())
+}
+/// 不用颜色时去掉所有样式,只输出字符
+fn paint<D: Display + Clone>(no_color: bool, content: StyledContent<D>) -> StyledContent<D> {
+    if no_color {
+        StyledContent::new(ContentStyle::new(), content.content().clone())
+    } else {
+        content
+    }
+}
+/// 截取不超过width列的前缀
+fn clip_to_width(text: &str, width: u16) -> String {
+    let mut used = 0;
+    text.chars()
+        .take_while(|&c| {
+            used += display_width(c.encode_utf8(&mut [0; 4]));
+            used <= width
+        })
+        .collect()
+}
+/// 恢复终端状态,panic时使用,不用备用屏幕时换一行,后面的输出不会接在画面上
+fn restore_terminal(alternate_screen: bool) {
+    let mut out = stdout();
+    let _ = disable_raw_mode();
+    let _ = out.execute(DisableFocusChange);
+    if alternate_screen {
+        let _ = out.execute(LeaveAlternateScreen);
+    } else {
+        let _ = out.execute(Print("\r\n"));
+    }
+    let _ = out.execute(Show);
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_snake_stress() {
+        //一整行长的蛇在穿墙模式里一直往右绕,每帧只有蛇头和蛇尾两格要动,
+        //逻辑和渲染都不能随蛇长变慢
+        let length = 1999;
+        let mut game = GameConfig::new()
+            .size(length + 1, 20)
+            .seed(1)
+            .wrap(true)
+            .initial_snake((length - 1, 10), length, Direction::Right)
+            .build_with_writer(Vec::new())
+            .unwrap();
+        game.food_spawner(FirstEmpty);
+        game.build_default().unwrap();
+        game.render_all().unwrap();
+        let start = Instant::now();
+        for _ in 0..10_000 {
+            game.writer.clear();
+            assert_eq!(game.step(None).unwrap(), StepResult::Moved);
+            game.render_only_updated().unwrap();
+            assert!(game.writer.len() < 500, "{} bytes", game.writer.len());
+        }
+        assert_eq!(game.snake_cells().count(), length);
+        assert!(
+            start.elapsed() < Duration::from_secs(20),
+            "{:?}",
+            start.elapsed()
+        );
+    }
+
+    /// 布置好场景、画过一遍的测试用游戏,输出写到内存里
+    fn drawn_game() -> Game<Vec<u8>> {
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .seed(1)
+            .build_with_writer(Vec::new())
+            .unwrap();
+        game.build_default().unwrap();
+        game.render_all().unwrap();
+        game.writer.clear();
+        game
+    }
+
+    #[test]
+    fn unchanged_dirty_cell_is_not_redrawn() {
+        let mut game = drawn_game();
+        //标记成要渲染,但样子和上次画的一样
+        game.set_cell(0, 0, CellType::Wall);
+        assert_eq!(game.dirty, vec![(0, 0)]);
+        game.render_only_updated().unwrap();
+        assert!(game.writer.is_empty());
+        assert!(game.dirty.is_empty());
+    }
+
+    #[test]
+    fn changed_cell_is_redrawn() {
+        let mut game = drawn_game();
+        game.set_cell(30, 5, CellType::Poison);
+        game.render_only_updated().unwrap();
+        assert!(!game.writer.is_empty());
+        //画过之后再标记一次也不用重画
+        game.writer.clear();
+        game.set_cell(30, 5, CellType::Poison);
+        game.render_only_updated().unwrap();
+        assert!(game.writer.is_empty());
+    }
+
+    #[test]
+    fn straight_body_lies_behind_head_in_every_direction() {
+        let head = (20, 10);
+        for direction in [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ] {
+            let snack = Snack::straight(head, 5, direction, (60, 20)).unwrap();
+            let (d_x, d_y) = direction.opposite().delta();
+            assert_eq!(snack.bodys.len(), 4);
+            //每一节都在前一节背后一格
+            let mut prev = head;
+            for &(x, y) in snack.bodys.iter() {
+                assert_eq!(
+                    (x as isize - prev.0 as isize, y as isize - prev.1 as isize),
+                    (d_x, d_y),
+                    "{:?}",
+                    direction
+                );
+                prev = (x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn tail_is_drawn_with_tail_glyph() {
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .distinct_tail(true)
+            .build_with_writer(std::io::sink())
+            .unwrap();
+        for &(x, y) in game.snack.bodys.iter() {
+            game.cells[x][y].cell_type = CellType::SnackBody;
+        }
+        game.update_tail();
+        let old_tail = *game.snack.bodys.back().unwrap();
+        let (x, y) = game.next_position(game.snack.direction).unwrap();
+        game.go(x, y);
+        game.update_tail();
+        let (x, y) = *game.snack.bodys.back().unwrap();
+        assert!(game.cells[x][y].tail);
+        assert!(!game.cells[old_tail.0][old_tail.1].tail);
+        let look = game.look();
+        assert_eq!(
+            *game.cells[x][y].cell_style_content(&look, None).content(),
+            '~'
+        );
+    }
+
+    #[test]
+    fn conflicting_turns_follow_input_priority() {
+        let queued = |priority| {
+            let mut game = GameConfig::new()
+                .size(60, 20)
+                .input_priority(priority)
+                .build_with_writer(std::io::sink())
+                .unwrap();
+            //同一帧先按w再按s
+            game.queue_key_turn(Direction::Up, KeyCode::Char('w'));
+            game.queue_key_turn(Direction::Down, KeyCode::Char('s'));
+            let queued = game
+                .input_queue
+                .iter()
+                .map(|&(dir, _)| dir)
+                .collect::<Vec<_>>();
+            //下一帧按的不和上一帧的比
+            game.input_queue.clear();
+            game.ticks += 1;
+            game.queue_key_turn(Direction::Left, KeyCode::Char('a'));
+            assert_eq!(game.input_queue.back().unwrap().0, Direction::Left);
+            queued
+        };
+        use Direction::*;
+        assert_eq!(queued(InputPriority::Buffered), vec![Up, Down]);
+        assert_eq!(queued(InputPriority::LastWins), vec![Down]);
+        assert_eq!(queued(InputPriority::FirstWins), vec![Up]);
+        //先按w再按下方向键
+        let mixed = |priority| {
+            let mut game = GameConfig::new()
+                .size(60, 20)
+                .input_priority(priority)
+                .build_with_writer(std::io::sink())
+                .unwrap();
+            game.queue_key_turn(Direction::Up, KeyCode::Char('w'));
+            game.queue_key_turn(Direction::Down, KeyCode::Down);
+            game.input_queue.back().unwrap().0
+        };
+        assert_eq!(mixed(InputPriority::LettersFirst), Direction::Up);
+        assert_eq!(mixed(InputPriority::ArrowsFirst), Direction::Down);
+    }
+
+    #[test]
+    fn food_blinks_only_close_to_expiry() {
+        use crossterm::style::Attribute;
+        let look = |blink| Look {
+            blink,
+            theme: Theme::classic(),
+            glyphs: Glyphs::block(),
+            background: BackgroundStyle::Solid,
+            cell_width: 1,
+            body_gradient: false,
+            no_color: false,
+        };
+        let blinks = |cell: &mut Cell, blink| {
+            let content = cell.cell_style_content(&look(blink), None);
+            content.style().attributes.has(Attribute::SlowBlink)
+        };
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .blink_before_expiry(5)
+            .build_with_writer(std::io::sink())
+            .unwrap();
+        game.cells[50][3].set_type(CellType::Food);
+        assert!(blinks(&mut game.cells[50][3], FoodBlink::Always));
+        assert!(!blinks(&mut game.cells[50][3], FoodBlink::NearExpiry));
+        assert!(!blinks(&mut game.cells[50][3], FoodBlink::Off));
+        //奖励食物离消失还剩5帧时才开始闪
+        game.cells[20][3].set_type(CellType::BonusFood);
+        game.bonus = Some(((20, 3), 6));
+        game.update_food_blink();
+        assert!(!blinks(&mut game.cells[20][3], FoodBlink::NearExpiry));
+        game.bonus = Some(((20, 3), 5));
+        game.update_food_blink();
+        assert!(blinks(&mut game.cells[20][3], FoodBlink::NearExpiry));
+        //会烂的普通食物离烂掉还剩5帧时也开始闪
+        game.food_lifetime = Some((20, 0));
+        game.foods = vec![((50, 3), 0)];
+        game.ticks = 14;
+        game.update_food_blink();
+        assert!(!blinks(&mut game.cells[50][3], FoodBlink::NearExpiry));
+        game.ticks = 15;
+        game.update_food_blink();
+        assert!(blinks(&mut game.cells[50][3], FoodBlink::NearExpiry));
+    }
+
+    #[test]
+    fn advance_uses_queued_turns_and_stops_at_death() {
+        let mut game = Game::with_writer(std::io::sink(), 60, 20).unwrap();
+        game.build_default().unwrap();
+        game.queue_direction(Direction::Down);
+        assert!(game.advance(2).unwrap().is_none());
+        assert_eq!(game.snack.head, (9, 9));
+        //一直往下会撞到下边的墙
+        assert!(matches!(
+            game.advance(100).unwrap(),
+            Some(GameOutcome::WallCollision)
+        ));
+        assert_eq!(game.snack.head.1, 18);
+    }
+
+    #[test]
+    fn food_never_spawns_on_the_nearly_full_board_snake() {
+        //蛇铺满了除最后一行以外的格子,空格都在蛇头前面,吃掉后新食物要按移动后的棋盘放
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .seed(5)
+            .pre_grown_snake(58 * 17 + 1)
+            .build_headless()
+            .unwrap();
+        let mut eaten = 0;
+        loop {
+            let result = game.step(None).unwrap();
+            let snake = game.snake_cells().collect::<Vec<_>>();
+            for food in game.food_positions() {
+                assert!(!snake.contains(&food), "{:?}", food);
+            }
+            match result {
+                StepResult::AteFood => eaten += 1,
+                StepResult::Moved => {}
+                _ => break,
+            }
+        }
+        assert!(eaten > 0);
+    }
+
+    #[test]
+    fn moving_off_a_borderless_map_hits_a_wall_on_every_edge() {
+        //地图必须有起点,放在右上角,开局的蛇用initial_snake另外指定
+        let open_map = format!("{}S\n", ".".repeat(59)) + &(".".repeat(60) + "\n").repeat(19);
+        for (head, direction) in [
+            ((0, 10), Direction::Left),
+            ((59, 10), Direction::Right),
+            ((30, 0), Direction::Up),
+            ((30, 19), Direction::Down),
+        ] {
+            let mut game = GameConfig::new()
+                .map(&open_map)
+                .seed(1)
+                .initial_snake(head, 3, direction)
+                .build_headless()
+                .unwrap();
+            assert_eq!(game.snack.head, head);
+            assert_eq!(
+                game.step(None).unwrap(),
+                StepResult::HitWall,
+                "{:?}",
+                direction
+            );
+        }
+    }
+
+    /// 棋盘正中朝右的直线蛇,蛇头前面一格是刀刃
+    fn blade_game(blade_min_length: usize) -> Game<std::io::Sink> {
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .initial_direction(Direction::Right)
+            .blades(vec![(31, 10)])
+            .blade_min_length(blade_min_length)
+            .build_with_writer(std::io::sink())
+            .unwrap();
+        game.build_default().unwrap();
+        game
+    }
+
+    #[test]
+    fn blade_cuts_off_the_back_half() {
+        let mut game = blade_game(3);
+        assert!(game.advance(1).unwrap().is_none());
+        assert_eq!(game.snack.head, (31, 10));
+        assert_eq!(game.snack.bodys.len(), 3);
+        //砍掉的格子清空了
+        assert_eq!(game.cells[28][10].cell_type, CellType::SnackBody);
+        assert_eq!(game.cells[27][10].cell_type, CellType::Empty);
+        assert_eq!(game.cells[24][10].cell_type, CellType::Empty);
+    }
+
+    #[test]
+    fn blade_ends_the_game_when_too_short() {
+        let mut game = blade_game(5);
+        assert!(matches!(
+            game.advance(1).unwrap(),
+            Some(GameOutcome::BladeCollision)
+        ));
+    }
+
+    #[test]
+    fn blade_death_animates_the_whole_snake() {
+        let mut game = blade_game(5);
+        game.advance(1).unwrap();
+        let cells = game.dead_snake_cells(GameOutcome::BladeCollision);
+        assert_eq!(cells.len(), game.snack.bodys.len() + 1);
+        //从尾巴闪到头
+        assert_eq!(cells.first(), game.snack.bodys.back());
+        assert_eq!(cells.last(), Some(&game.snack.head));
+        assert!(game.dead_snake_cells(GameOutcome::Quit).is_empty());
+    }
+
+    #[test]
+    fn wrapping_twice_the_same_way_awards_lap_bonus() {
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .lap_bonus(5)
+            .build_with_writer(std::io::sink())
+            .unwrap();
+        //第一次穿墙之前是从中间出发的,不算一圈
+        game.check_lap(true);
+        assert_eq!(game.score, 0);
+        game.check_lap(false);
+        game.check_lap(true);
+        assert_eq!(game.score, 5);
+        assert!(game.lap_message > 0);
+        //换个方向穿墙重新算
+        game.snack.moved = Direction::Up;
+        game.check_lap(true);
+        assert_eq!(game.score, 5);
+    }
+
+    #[test]
+    fn opposing_turns_in_one_frame_never_reverse() {
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .seed(1)
+            .initial_snake((20, 10), 5, Direction::Right)
+            .build_headless()
+            .unwrap();
+        //一帧内先上再左,左是这一帧实际走的方向的反方向,不能生效
+        assert!(game.steer(Direction::Up));
+        assert!(!game.steer(Direction::Left));
+        assert_eq!(game.step(None).unwrap(), StepResult::Moved);
+        assert_eq!(game.snack.head, (20, 9));
+        //往上走时先右再下,下也是反方向
+        assert!(game.steer(Direction::Right));
+        assert!(!game.steer(Direction::Down));
+        assert_eq!(game.step(Some(Direction::Down)).unwrap(), StepResult::Moved);
+        assert_eq!(game.snack.head, (21, 9));
+    }
+
+    #[test]
+    fn same_seed_places_the_same_food() {
+        let foods = |seed| {
+            let mut game = GameConfig::new()
+                .size(60, 20)
+                .seed(seed)
+                .build_headless()
+                .unwrap();
+            let mut foods = Vec::new();
+            for _ in 0..5 {
+                let (x, y) = game.foods[0].0;
+                foods.push((x, y));
+                game.remove_food((x, y));
+                game.set_cell(x, y, CellType::Empty);
+                game.fill_food();
+            }
+            foods
+        };
+        assert_eq!(foods(7), foods(7));
+        assert_ne!(foods(7), foods(8));
+    }
+
+    #[test]
+    fn set_speed_and_speed_keys_share_one_range() {
+        let mut game = GameConfig::new().size(60, 20).build_headless().unwrap();
+        game.set_speed(1).unwrap();
+        assert_eq!(game.speed(), MIN_SPEED);
+        game.set_speed(5000).unwrap();
+        assert_eq!(game.speed(), MAX_SPEED);
+    }
+
+    #[test]
+    fn turn_keys_react_to_press_and_repeat_but_not_release() {
+        use crossterm::event::KeyModifiers;
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .seed(1)
+            .build_headless()
+            .unwrap();
+        let mut next_tick = Instant::now();
+        let mut press = |game: &mut Game<std::io::Sink>, code, kind| {
+            let event = KeyEvent::new_with_kind(code, KeyModifiers::NONE, kind);
+            game.handle_key(event, &mut next_tick).unwrap();
+            game.input_queue.back().map(|&(dir, _)| dir)
+        };
+        let release = KeyEventKind::Release;
+        assert_eq!(press(&mut game, KeyCode::Up, release), None);
+        let up = press(&mut game, KeyCode::Up, KeyEventKind::Press);
+        assert_eq!(up, Some(Direction::Up));
+        let down = press(&mut game, KeyCode::Char('s'), KeyEventKind::Repeat);
+        assert_eq!(down, Some(Direction::Down));
+        assert_eq!(
+            press(&mut game, KeyCode::Left, release),
+            Some(Direction::Down)
+        );
+        //暂停只认按下
+        press(&mut game, KeyCode::Char(' '), release);
+        assert!(!game.is_paused());
+        press(&mut game, KeyCode::Char(' '), KeyEventKind::Press);
+        assert!(game.is_paused());
+    }
+
+    #[test]
+    fn speed_step_and_debug_keys_come_from_key_bindings() {
+        use crossterm::event::KeyModifiers;
+        let press = |game: &mut Game<std::io::Sink>, code| {
+            let event = KeyEvent::new(code, KeyModifiers::NONE);
+            game.handle_key(event, &mut Instant::now()).unwrap();
+        };
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .seed(1)
+            .speed(100)
+            .build_headless()
+            .unwrap();
+        press(&mut game, KeyCode::Char('+'));
+        assert_eq!(game.speed, 100 - SPEED_KEY_STEP);
+        press(&mut game, KeyCode::Char('-'));
+        assert_eq!(game.speed, 100);
+        press(&mut game, KeyCode::F(3));
+        assert!(game.debug_overlay);
+        game.pause().unwrap();
+        press(&mut game, KeyCode::Char('.'));
+        assert_eq!(game.ticks, 1);
+        assert!(game.is_paused());
+        //换了绑定以后原来的键不再起作用
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .seed(1)
+            .speed(100)
+            .key_bindings(KeyBindings::classic().bind(KeyCode::Char('x'), Action::SpeedUp))
+            .build_headless()
+            .unwrap();
+        press(&mut game, KeyCode::Char('x'));
+        assert_eq!(game.speed, 100 - SPEED_KEY_STEP);
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .seed(1)
+            .speed(100)
+            .key_bindings(
+                KeyBindings::empty()
+                    .bind(KeyCode::Up, Action::Turn(Direction::Up))
+                    .bind(KeyCode::Char('x'), Action::SpeedUp),
+            )
+            .build_headless()
+            .unwrap();
+        press(&mut game, KeyCode::Char('+'));
+        press(&mut game, KeyCode::F(3));
+        assert_eq!(game.speed, 100);
+        assert!(!game.debug_overlay);
+    }
+
+    #[test]
+    fn key_tip_follows_the_bindings() {
+        let tip = |config: GameConfig| config.size(60, 20).build_headless().unwrap().key_tip();
+        assert_eq!(tip(GameConfig::new()), "空格暂停 +/-调速 P存档 按Esc退出");
+        assert_eq!(
+            tip(GameConfig::new().speed_keys(false)),
+            "空格暂停 P存档 按Esc退出"
+        );
+        //没绑定的操作不提示
+        let bindings = KeyBindings::empty()
+            .bind(KeyCode::Up, Action::Turn(Direction::Up))
+            .bind(KeyCode::Char('x'), Action::Pause)
+            .bind(KeyCode::Char('-'), Action::SpeedDown)
+            .bind(KeyCode::Char('q'), Action::Quit);
+        assert_eq!(
+            tip(GameConfig::new().key_bindings(bindings)),
+            "X暂停 -减速 按Q退出"
+        );
+        assert_eq!(
+            tip(GameConfig::new().key_bindings(KeyBindings::empty())),
+            ""
+        );
+    }
+
+    #[test]
+    fn quit_prompt_answers_come_from_key_bindings() {
+        use crossterm::event::KeyModifiers;
+        let press = |game: &mut Game<std::io::Sink>, code| {
+            let event = KeyEvent::new(code, KeyModifiers::NONE);
+            game.handle_key(event, &mut Instant::now()).unwrap()
+        };
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .seed(1)
+            .confirm_quit(true)
+            .key_bindings(
+                KeyBindings::classic()
+                    .bind(KeyCode::Enter, Action::Confirm)
+                    .bind(KeyCode::Backspace, Action::Cancel),
+            )
+            .build_headless()
+            .unwrap();
+        assert_eq!(press(&mut game, KeyCode::Esc), None);
+        assert!(game.is_paused());
+        //询问时其他键不算
+        assert_eq!(press(&mut game, KeyCode::Char(' ')), None);
+        assert_eq!(press(&mut game, KeyCode::Backspace), None);
+        assert!(!game.is_paused());
+        press(&mut game, KeyCode::Esc);
+        assert_eq!(press(&mut game, KeyCode::Char('N')), None);
+        press(&mut game, KeyCode::Esc);
+        assert_eq!(press(&mut game, KeyCode::Enter), Some(GameOutcome::Quit));
+        //提示用每个操作绑定的第一个键
+        assert_eq!(game.quit_prompt_text(), "退出? Y/N");
+        let game = GameConfig::new()
+            .size(60, 20)
+            .key_bindings(
+                KeyBindings::empty()
+                    .bind(KeyCode::Esc, Action::Quit)
+                    .bind(KeyCode::Enter, Action::Confirm)
+                    .bind(KeyCode::Backspace, Action::Cancel),
+            )
+            .build_headless()
+            .unwrap();
+        assert_eq!(game.quit_prompt_text(), "退出? Enter/Backspace");
+    }
+
+    #[test]
+    fn every_control_scheme_can_restart() {
+        for scheme in [
+            ControlScheme::Wasd,
+            ControlScheme::Arrows,
+            ControlScheme::Vim,
+            ControlScheme::Ijkl,
+        ] {
+            let bindings = scheme.bindings();
+            assert!(bindings.validate().is_ok(), "{:?}", scheme);
+            assert_eq!(
+                bindings.action(KeyCode::Char('r')),
+                Some(Action::Restart),
+                "{:?}",
+                scheme
+            );
+        }
+        assert_eq!(
+            KeyBindings::two_player().action(KeyCode::Char('R')),
+            Some(Action::Restart)
+        );
+    }
+
+    #[test]
+    fn smallest_valid_and_largest_invalid_board_sizes() {
+        assert!(validate_dimensions(MIN_WIDTH, MIN_HEIGHT, None).is_ok());
+        for (width, height) in [(MIN_WIDTH - 1, MIN_HEIGHT), (MIN_WIDTH, MIN_HEIGHT - 1)] {
+            assert!(matches!(
+                validate_dimensions(width, height, None),
+                Err(GameError::BoardTooSmall {
+                    min_width: MIN_WIDTH,
+                    min_height: MIN_HEIGHT,
+                    ..
+                })
+            ));
+            //每种创建方式都检查
+            let built = GameConfig::new()
+                .size(width, height)
+                .build_with_writer(Vec::new());
+            assert!(matches!(built, Err(GameError::BoardTooSmall { .. })));
+        }
+        assert!(GameConfig::new()
+            .size(MIN_WIDTH, MIN_HEIGHT)
+            .build_headless()
+            .is_ok());
+        let mut game = drawn_game();
+        assert!(matches!(
+            game.resize(MIN_WIDTH - 1, MIN_HEIGHT),
+            Err(GameError::BoardTooSmall { .. })
+        ));
+    }
+
+    #[test]
+    fn small_boards_only_need_room_for_the_placed_snake() {
+        //最小尺寸只限制默认的开局,自己放的蛇和地图按实际的蛇检查
+        assert!(GameConfig::new()
+            .size(20, 8)
+            .initial_snake((10, 4), 3, Direction::Right)
+            .build_headless()
+            .is_ok());
+        assert!(GameConfig::new()
+            .map("#####\n#S..#\n#####\n")
+            .build_headless()
+            .is_ok());
+        assert!(GameConfig::new()
+            .size(20, 8)
+            .pre_grown_snake(10)
+            .build_headless()
+            .is_ok());
+        assert!(matches!(
+            GameConfig::new().size(20, 8).build_headless(),
+            Err(GameError::BoardTooSmall { .. })
+        ));
+        //蛇之外没有放食物的格子
+        let snack = Snack::straight((3, 0), 4, Direction::Right, (4, 1)).unwrap();
+        assert!(matches!(
+            validate_dimensions(4, 1, Some(&snack)),
+            Err(GameError::BoardTooSmall {
+                min_width: 4,
+                min_height: 1,
+                ..
+            })
+        ));
+        assert!(validate_dimensions(5, 1, Some(&snack)).is_ok());
+    }
+
+    #[test]
+    fn head_can_follow_into_the_leaving_tail_tip() {
+        //蛇盘成2x2的一圈,往下转正好走进这一帧要空出来的蛇尾
+        let looped = |tail_chasing| {
+            let mut game = GameConfig::new()
+                .size(60, 20)
+                .seed(1)
+                .tail_chasing(tail_chasing)
+                .build_with_writer(Vec::new())
+                .unwrap();
+            game.snack = Snack {
+                direction: Direction::Left,
+                moved: Direction::Left,
+                head: (10, 10),
+                bodys: [(11, 10), (11, 11), (10, 11)].into_iter().collect(),
+            };
+            game.initial_snack = game.snack.clone();
+            game.build_default().unwrap();
+            game
+        };
+        let mut game = looped(true);
+        assert_eq!(game.step(Some(Direction::Down)).unwrap(), StepResult::Moved);
+        assert_eq!(game.snack.head, (10, 11));
+        assert_eq!(game.snake_len(), 4);
+        //还要长一节时蛇尾不动,走进去就是撞到自己
+        let mut game = looped(true);
+        game.pending_growth = 1;
+        assert_eq!(
+            game.step(Some(Direction::Down)).unwrap(),
+            StepResult::HitSelf
+        );
+        let mut game = looped(false);
+        assert_eq!(
+            game.step(Some(Direction::Down)).unwrap(),
+            StepResult::HitSelf
+        );
+    }
+
+    #[test]
+    fn building_fails_when_walls_cover_the_start_or_leave_no_food_cell() {
+        //8格厚的边界墙压住默认开局的蛇(7..=9, 7..=10)
+        let thick = GameConfig::new()
+            .size(60, 20)
+            .border_thickness(8)
+            .build_with_writer(Vec::new());
+        assert!(matches!(thick, Err(GameError::SnakeOnWall)));
+        //地图内部的墙和传送门上都不能放蛇
+        let map = (0..20)
+            .map(|y| {
+                (0..60)
+                    .map(|x| match (x, y) {
+                        (0 | 59, _) | (_, 0 | 19) => '#',
+                        (20..=30, 10) => '#',
+                        (40, 5) | (40, 15) => '1',
+                        (2, 2) => 'S',
+                        _ => '.',
+                    })
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect::<String>();
+        for head in [(25, 10), (40, 5)] {
+            let built = GameConfig::new()
+                .map(&map)
+                .initial_snake(head, 1, Direction::Right)
+                .build_with_writer(Vec::new());
+            assert!(matches!(built, Err(GameError::SnakeOnWall)), "{:?}", head);
+        }
+        assert!(GameConfig::new()
+            .map(&map)
+            .build_with_writer(Vec::new())
+            .is_ok());
+        //除了起点全是墙,放不下第一个食物
+        let full = format!("S{}\n", "#".repeat(59)) + &("#".repeat(60) + "\n").repeat(19);
+        let built = GameConfig::new().map(&full).build_with_writer(Vec::new());
+        assert!(matches!(built, Err(GameError::NoSpaceForFood)));
+    }
+
+    /// 40x10一块里每隔step格改一次,来回在墙和空地之间切换
+    fn toggle_block(game: &mut Game<Vec<u8>>, step: usize, wall: bool) {
+        let cell_type = if wall {
+            CellType::Wall
+        } else {
+            CellType::Empty
+        };
+        for (x, y) in (10..50)
+            .flat_map(|x| (5..15).map(move |y| (x, y)))
+            .step_by(step)
+        {
+            game.set_cell(x, y, cell_type);
+        }
+    }
+
+    #[test]
+    fn dense_changes_are_drawn_as_one_region() {
+        let mut game = drawn_game();
+        toggle_block(&mut game, 1, true);
+        let dirty = game.dirty.clone();
+        game.draw_cells(&dirty).unwrap();
+        let per_cell = std::mem::take(&mut game.writer).len();
+        toggle_block(&mut game, 1, false);
+        game.render_only_updated().unwrap();
+        assert!(
+            game.writer.len() < per_cell,
+            "{} {}",
+            game.writer.len(),
+            per_cell
+        );
+    }
+
+    #[test]
+    #[ignore = "性能对比,用cargo test --release -- --ignored --nocapture运行"]
+    fn bench_cell_region_and_full_redraw() {
+        const FRAMES: u32 = 500;
+        for (density, step) in [("密集", 1), ("稀疏", 7)] {
+            for strategy in ["逐格", "整块", "全屏"] {
+                let mut game = drawn_game();
+                let mut bytes = 0;
+                let start = Instant::now();
+                for frame in 0..FRAMES {
+                    toggle_block(&mut game, step, frame % 2 == 0);
+                    let dirty = std::mem::take(&mut game.dirty);
+                    game.writer.clear();
+                    match strategy {
+                        "逐格" => game.draw_cells(&dirty).unwrap(),
+                        "整块" => {
+                            let (xs, ys) = dirty_region(&dirty).unwrap();
+                            game.draw_region(xs, ys).unwrap();
+                        }
+                        //全部格子都当作没画过,整个棋盘重画
+                        _ => {
+                            for cell in game.cells.iter_mut().flatten() {
+                                cell.invalidate();
+                            }
+                            game.render_all().unwrap();
+                        }
+                    }
+                    bytes += game.writer.len();
+                }
+                eprintln!(
+                    "{}{}: 每帧{:?}, {}字节",
+                    density,
+                    strategy,
+                    start.elapsed() / FRAMES,
+                    bytes / FRAMES as usize
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_seed_places_a_known_food_sequence() {
+        let game = GameConfig::new()
+            .size(60, 20)
+            .seed(42)
+            .food_count(6)
+            .build_headless()
+            .unwrap();
+        //空格子按行从上到下、行内从左到右
+        let mut empty = Vec::new();
+        game.collect_empty_cells(&mut empty);
+        assert!(empty
+            .windows(2)
+            .all(|pair| (pair[0].1, pair[0].0) < (pair[1].1, pair[1].0)));
+        //这串位置变了,说明空格子的顺序或随机数的用法变了,固定种子的局面都会跟着变
+        assert_eq!(
+            game.food_positions(),
+            [(48, 10), (19, 8), (29, 8), (19, 16), (20, 3), (4, 1)]
+        );
+    }
+
+    #[test]
+    fn invincible_wall_hits_do_not_pause_like_wall_grace() {
+        let facing_wall = |wall_grace| {
+            let mut game = GameConfig::new()
+                .size(60, 20)
+                .seed(1)
+                .wall_grace(wall_grace)
+                .initial_snake((58, 10), 3, Direction::Right)
+                .build_headless()
+                .unwrap();
+            game.food_spawner(FirstEmpty);
+            game
+        };
+        let start = Instant::now();
+        let mut game = facing_wall(false);
+        game.invuln_frames = 3;
+        for _ in 0..3 {
+            let mut next_tick = start;
+            assert_eq!(game.tick(&mut next_tick).unwrap(), None);
+            assert_eq!(next_tick, start);
+            assert_eq!(game.snack.head, (58, 10));
+        }
+        assert_eq!(game.step(None).unwrap(), StepResult::HitWall);
+        //宽限只在刚撞上那一帧停顿
+        let mut game = facing_wall(true);
+        let mut next_tick = start;
+        assert_eq!(game.tick(&mut next_tick).unwrap(), None);
+        assert_eq!(next_tick, start + Duration::from_millis(WALL_GRACE_PAUSE));
+    }
+
+    #[test]
+    fn body_stays_drawn_where_an_invincible_snake_crossed_itself() {
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .seed(1)
+            .initial_snake((20, 10), 10, Direction::Right)
+            .build_headless()
+            .unwrap();
+        game.food_spawner(FirstEmpty);
+        game.invuln_frames = 3;
+        //绕一个小圈从自己身上(19, 10)穿过去,无敌结束后那一格还压着蛇身
+        let turns = [Direction::Up, Direction::Left, Direction::Down]
+            .into_iter()
+            .chain([Direction::Down; 6])
+            .chain([Direction::Right; 6]);
+        for dir in turns {
+            assert_eq!(game.step(Some(dir)).unwrap(), StepResult::Moved);
+            let head = game.snack.head;
+            for (x, y) in game.snake_cells().filter(|&pos| pos != head) {
+                assert_eq!(
+                    game.cell_type(x, y),
+                    Some(CellType::SnackBody),
+                    "{:?}",
+                    (x, y)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn stale_queued_turn_is_dropped() {
+        let mut queue = VecDeque::new();
+        push_direction(&mut queue, Direction::Up, 0);
+        assert_eq!(pop_direction(&mut queue, 3, Some(2)), None);
+        //没超时的照常取出
+        push_direction(&mut queue, Direction::Up, 3);
+        assert_eq!(pop_direction(&mut queue, 4, Some(2)), Some(Direction::Up));
+        //不限制时多久都取得出来
+        push_direction(&mut queue, Direction::Left, 0);
+        assert_eq!(pop_direction(&mut queue, 100, None), Some(Direction::Left));
+    }
+
+    #[test]
+    fn vertical_snake_spawns_from_config() {
+        let game = GameConfig::new()
+            .size(60, 20)
+            .initial_direction(Direction::Up)
+            .build_with_writer(std::io::sink())
+            .unwrap();
+        assert_eq!(game.snack.head, (30, 10));
+        let bodys = game.snack.bodys.iter().copied().collect::<Vec<_>>();
+        assert_eq!(bodys, (11..18).map(|y| (30, y)).collect::<Vec<_>>());
     }
 }
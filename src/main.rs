@@ -1,12 +1,87 @@
-use r_snack::Game;
+use r_snack::GameConfig;
+
+/// 命令行用法
+const USAGE: &str = "用法: r_snack [选项]
+
+选项:
+  --width <格数>    棋盘宽度,要和--height一起设置,不设置时铺满终端
+  --height <格数>   棋盘高度
+  --speed <毫秒>    开局每帧间隔
+  --wrap            穿墙模式
+  --walls           撞墙模式
+  --level <名字>    内置关卡: classic、donut、cross、spiral
+  --daily           今天的每日挑战,棋盘固定60x20,同一天大家的食物都一样
+  --inline          不切到备用屏幕,退出后画面留在终端里
+  --big-hud         在棋盘右边显示大分数面板,终端要比棋盘宽
+  -h, --help        显示这个帮助
+
+设置了速度或模式时不显示开始菜单";
 
 fn main() {
-    let mut g = Game::new().unwrap_or_else(|e| {
-        println!("初始化游戏失败: {}", e);
-        std::process::exit(0);
+    let config = parse_args(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, USAGE);
+        std::process::exit(2);
+    });
+    let Some(config) = config else {
+        println!("{}", USAGE);
+        return;
+    };
+    let mut g = config.build().unwrap_or_else(|e| {
+        eprintln!("初始化游戏失败: {}", e);
+        std::process::exit(1);
     });
-    if let Err(e) = g.run() {
-        println!("fail: {}", e);
+    match g.run() {
+        Ok((outcome, score)) => println!("{},得分: {}", outcome, score),
+        Err(e) => {
+            eprintln!("fail: {}", e);
+            std::process::exit(1);
+        }
     }
-    println!("得分: {}", g.score());
+}
+/// 按命令行参数生成配置,有--help时返回None
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<GameConfig>, String> {
+    let mut config = GameConfig::new();
+    let (mut width, mut height) = (None, None);
+    let mut menu = true;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--width" => width = Some(parse_value(&arg, args.next())?),
+            "--height" => height = Some(parse_value(&arg, args.next())?),
+            "--speed" => {
+                let speed = parse_value(&arg, args.next())?;
+                if speed == 0 {
+                    return Err("--speed必须大于0".to_string());
+                }
+                config = config.speed(speed);
+                menu = false;
+            }
+            "--level" => {
+                let name = args.next().ok_or("--level后面缺少关卡名")?;
+                config = config.level(&name);
+                menu = false;
+            }
+            "--daily" => config = config.daily(),
+            "--inline" => config = config.alternate_screen(false),
+            "--big-hud" => config = config.big_hud(true),
+            "--wrap" | "--walls" => {
+                config = config.wrap(arg == "--wrap");
+                menu = false;
+            }
+            _ => return Err(format!("未知参数: {}", arg)),
+        }
+    }
+    match (width, height) {
+        (Some(width), Some(height)) => config = config.size(width, height),
+        (None, None) => {}
+        _ => return Err("--width和--height要一起设置".to_string()),
+    }
+    Ok(Some(config.menu(menu)))
+}
+/// 解析选项后面的数字
+fn parse_value<T: std::str::FromStr>(name: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{}后面缺少数值", name))?;
+    value
+        .parse()
+        .map_err(|_| format!("{}的值无效: {}", name, value))
 }
@@ -0,0 +1,84 @@
+use crate::GameError;
+
+/// 墙
+const WALL: char = '#';
+/// 空地
+const EMPTY: char = '.';
+/// 蛇头起点
+const START: char = 'S';
+/// 刀刃
+const BLADE: char = 'X';
+
+/// 从文本解析出的地图
+pub(crate) struct Map {
+    pub width: usize,
+    pub height: usize,
+    /// 所有墙的坐标
+    pub walls: Vec<(usize, usize)>,
+    /// 刀刃的坐标
+    pub blades: Vec<(usize, usize)>,
+    /// 蛇头起点
+    pub start: (usize, usize),
+    /// 成对的传送门
+    pub portals: Vec<((usize, usize), (usize, usize))>,
+}
+impl Map {
+    /// 解析地图,`#`是墙,`.`是空地,`S`是蛇头起点,`X`是刀刃,相同的数字是一对传送门,空行忽略
+    pub fn parse(map: &str) -> Result<Self, GameError> {
+        let rows = map
+            .lines()
+            .map(|line| line.trim_end())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>();
+        let height = rows.len();
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        let mut walls = Vec::new();
+        let mut blades = Vec::new();
+        let mut start = None;
+        let mut portal_ends: [Vec<(usize, usize)>; 10] = Default::default();
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(GameError::InvalidMap(format!("第{}行宽度不一致", y + 1)));
+            }
+            for (x, c) in row.chars().enumerate() {
+                match c {
+                    WALL => walls.push((x, y)),
+                    BLADE => blades.push((x, y)),
+                    EMPTY => {}
+                    START if start.is_none() => start = Some((x, y)),
+                    START => return Err(GameError::InvalidMap("地图中有多个起点".to_string())),
+                    '0'..='9' => portal_ends[c as usize - '0' as usize].push((x, y)),
+                    _ => {
+                        return Err(GameError::InvalidMap(format!(
+                            "第{}行有未知字符'{}'",
+                            y + 1,
+                            c
+                        )))
+                    }
+                }
+            }
+        }
+        let start = start.ok_or_else(|| GameError::InvalidMap("地图中没有起点".to_string()))?;
+        let mut portals = Vec::new();
+        for (digit, ends) in portal_ends.iter().enumerate() {
+            match ends[..] {
+                [] => {}
+                [a, b] => portals.push((a, b)),
+                _ => {
+                    return Err(GameError::InvalidMap(format!(
+                        "传送门{}必须正好有两个",
+                        digit
+                    )))
+                }
+            }
+        }
+        Ok(Map {
+            width,
+            height,
+            walls,
+            blades,
+            start,
+            portals,
+        })
+    }
+}
@@ -0,0 +1,96 @@
+use std::io::{Result as IOResult, Write};
+
+use crossterm::{
+    cursor::MoveTo,
+    style::{PrintStyledContent, Stylize},
+    QueueableCommand,
+};
+
+use crate::{paint, CellType, Game};
+
+/// 缩小显示时一块里有多种格子,按这个顺序选一种显示
+const BLOCK_PRIORITY: [CellType; 11] = [
+    CellType::SnackHead,
+    CellType::Snack2Head,
+    CellType::SnackBody,
+    CellType::Snack2Body,
+    CellType::Food,
+    CellType::BonusFood,
+    CellType::Runner,
+    CellType::Poison,
+    CellType::Blade,
+    CellType::Portal,
+    CellType::Wall,
+];
+
+impl<W: Write> Game<W> {
+    /// 屏幕上显示的列数和行数,缩小显示时一个字符代表一块格子,方格显示时一格占两列
+    pub(crate) fn screen_size(&self) -> (usize, usize) {
+        let (width, height) = self.size();
+        let (width, height) = match self.zoom {
+            Some((block_w, block_h)) => (width.div_ceil(block_w), height.div_ceil(block_h)),
+            None => (width, height),
+        };
+        (width * self.cell_width as usize, height)
+    }
+    /// 屏幕(x, y)位置下次渲染时重画
+    pub(crate) fn invalidate_screen(&mut self, x: usize, y: usize) {
+        let (block_w, block_h) = self.zoom.unwrap_or((1, 1));
+        let (width, height) = self.size();
+        let (x, y) = (x / self.cell_width as usize * block_w, y * block_h);
+        if x < width && y < height {
+            self.invalidate_cell(x, y);
+        }
+    }
+    /// 缩小显示时渲染包含这些格子的块
+    pub(crate) fn render_blocks<I: IntoIterator<Item = (usize, usize)>>(
+        &mut self,
+        cells: I,
+    ) -> IOResult<()> {
+        let Some((block_w, block_h)) = self.zoom else {
+            return Ok(());
+        };
+        let mut blocks = cells
+            .into_iter()
+            .map(|(x, y)| (x / block_w, y / block_h))
+            .collect::<Vec<_>>();
+        blocks.sort_unstable();
+        blocks.dedup();
+        let (width, height) = self.size();
+        for (bx, by) in blocks {
+            let xs = bx * block_w..((bx + 1) * block_w).min(width);
+            let ys = by * block_h..((by + 1) * block_h).min(height);
+            let mut shown = CellType::Empty;
+            for x in xs {
+                for y in ys.clone() {
+                    let cell = &mut self.cells[x][y];
+                    cell.changed_flag = false;
+                    cell.rendered = None;
+                    let rank = |t| BLOCK_PRIORITY.iter().position(|&p| p == t);
+                    if rank(cell.cell_type).unwrap_or(usize::MAX)
+                        < rank(shown).unwrap_or(usize::MAX)
+                    {
+                        shown = cell.cell_type;
+                    }
+                }
+            }
+            let glyph = if shown == CellType::SnackHead {
+                self.glyphs.head(self.snack.moved)
+            } else {
+                self.glyphs.get(shown)
+            };
+            let text = glyph.to_string().repeat(self.cell_width as usize);
+            self.writer
+                .queue(MoveTo(
+                    bx as u16 * self.cell_width + self.offset.0,
+                    by as u16 + self.offset.1,
+                ))?
+                .queue(PrintStyledContent(paint(
+                    self.no_color,
+                    text.with(self.theme.color(shown)).on(self.theme.background),
+                )))?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
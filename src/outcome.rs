@@ -0,0 +1,60 @@
+use std::{fmt, time::Duration};
+
+use crate::Winner;
+
+/// 一局正常结束的原因
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum GameOutcome {
+    /// 玩家按了退出
+    Quit,
+    /// 撞墙
+    WallCollision,
+    /// 撞到自己
+    SelfCollision,
+    /// 被刀刃砍断后剩下的太短
+    BladeCollision,
+    /// 蛇占满了棋盘,没有地方放食物,赢了
+    Won,
+    /// 限时模式时间到
+    TimeUp,
+    /// 关卡模式通关
+    Victory,
+    /// 竞速模式达到目标分数,带上用时
+    TargetReached(Duration),
+    /// 蛇长到了目标长度,带上最后的长度
+    LengthReached(usize),
+    /// 双人模式分出胜负
+    TwoPlayerOver(Winner),
+}
+impl GameOutcome {
+    /// 是不是赢了
+    pub fn is_win(&self) -> bool {
+        matches!(
+            self,
+            GameOutcome::Won
+                | GameOutcome::Victory
+                | GameOutcome::TargetReached(_)
+                | GameOutcome::LengthReached(_)
+        )
+    }
+}
+impl fmt::Display for GameOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameOutcome::Quit => write!(f, "退出"),
+            GameOutcome::WallCollision => write!(f, "撞墙"),
+            GameOutcome::SelfCollision => write!(f, "自杀"),
+            GameOutcome::BladeCollision => write!(f, "被刀刃砍断"),
+            GameOutcome::Won => write!(f, "恭喜,蛇占满了棋盘"),
+            GameOutcome::TimeUp => write!(f, "时间到"),
+            GameOutcome::Victory => write!(f, "恭喜通关"),
+            GameOutcome::TargetReached(time) => {
+                write!(f, "用时{:.2}秒达到目标分数", time.as_secs_f64())
+            }
+            GameOutcome::LengthReached(len) => write!(f, "恭喜,蛇长到了{}节", len),
+            GameOutcome::TwoPlayerOver(Winner::PlayerOne) => write!(f, "玩家一获胜"),
+            GameOutcome::TwoPlayerOver(Winner::PlayerTwo) => write!(f, "玩家二获胜"),
+            GameOutcome::TwoPlayerOver(Winner::Draw) => write!(f, "平局"),
+        }
+    }
+}
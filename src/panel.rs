@@ -0,0 +1,53 @@
+use std::io::{Result as IOResult, Write};
+
+use crossterm::{
+    cursor::MoveTo,
+    style::{PrintStyledContent, Stylize},
+    QueueableCommand,
+};
+
+use crate::{display_width, paint, Game};
+
+/// 大分数面板的宽度
+pub(crate) const PANEL_WIDTH: usize = 14;
+/// 面板和棋盘之间空几列
+pub(crate) const PANEL_GAP: usize = 2;
+/// 面板上每项占几行:标签、数值和空行
+const ITEM_ROWS: usize = 3;
+/// 面板一共几行
+pub(crate) const PANEL_ROWS: usize = 5 * ITEM_ROWS;
+
+impl<W: Write> Game<W> {
+    /// 在棋盘右边画大分数面板,比分数栏显眼,直播时看得清,终端放不下面板时什么都不做
+    pub(crate) fn print_panel(&mut self) -> IOResult<()> {
+        let Some((x, y)) = self.panel else {
+            return Ok(());
+        };
+        let secs = self.stats.play_time.as_secs();
+        let items = [
+            ("得分", self.score.to_string()),
+            ("最高", self.session.best_score.max(self.score).to_string()),
+            ("速度", format!("{}ms", self.speed)),
+            ("长度", self.snake_len().to_string()),
+            ("用时", format!("{}:{:02}", secs / 60, secs % 60)),
+        ];
+        for (i, (label, value)) in items.into_iter().enumerate() {
+            let row = y + (i * ITEM_ROWS) as u16;
+            let pad = PANEL_WIDTH - display_width(label) as usize;
+            let label = format!("{}{}", label, " ".repeat(pad));
+            let value = format!("{:^width$}", value, width = PANEL_WIDTH);
+            let value = if i == 0 {
+                value.yellow().on_dark_blue().bold()
+            } else {
+                value.white().on_dark_blue().bold()
+            };
+            self.writer
+                .queue(MoveTo(x, row))?
+                .queue(PrintStyledContent(paint(self.no_color, label.grey())))?
+                .queue(MoveTo(x, row + 1))?
+                .queue(PrintStyledContent(paint(self.no_color, value)))?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use std::io::{Result as IOResult, Write};
+
+use crate::{CellType, Game};
+
+/// 画面的输出后端,设置后代替crossterm画棋盘和分数栏,可以接纯文本、图形界面、网页等,
+/// 不设置时默认用crossterm画到终端
+pub trait Renderer {
+    /// (x, y)这一格变成了kind,只有变了的格子才会调用
+    fn draw_cell(&mut self, x: usize, y: usize, kind: CellType);
+    /// 分数栏的内容,标签和数值按显示顺序排列
+    fn hud(&mut self, items: &[&str]);
+    /// 一帧画完了
+    fn flush(&mut self);
+}
+impl<W: Write> Game<W> {
+    /// 设置输出后端,替换之前设置的,通常和`GameConfig::build_headless()`一起用,
+    /// 标题、倒数、菜单这些提示还是写到原来的输出
+    pub fn renderer<R: Renderer + 'static>(&mut self, renderer: R) {
+        self.renderer = Some(Box::new(renderer));
+        for cell in self.cells.iter_mut().flatten() {
+            if cell.invalidate() {
+                self.dirty.push((cell.x, cell.y));
+            }
+        }
+    }
+    /// 画出变了的格子和分数栏,自己用`step()`推进时每帧调用一次
+    pub fn render(&mut self) -> IOResult<()> {
+        self.render_only_updated()?;
+        self.print_score()
+    }
+    /// 有输出后端时把待渲染的格子交给它,返回是否处理了
+    pub(crate) fn draw_with_renderer<I: IntoIterator<Item = (usize, usize)>>(
+        &mut self,
+        cells: I,
+    ) -> bool {
+        let Some(renderer) = self.renderer.as_mut() else {
+            return false;
+        };
+        for (x, y) in cells {
+            self.cells[x][y].draw(renderer.as_mut());
+        }
+        renderer.flush();
+        true
+    }
+}
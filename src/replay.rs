@@ -0,0 +1,265 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{sink, Sink, Write},
+    path::Path,
+};
+
+use crate::{
+    save::{
+        border_to_string, parse_border, parse_pos, parse_snack, pos_to_string, snack_to_string,
+    },
+    Direction, Game, GameConfig, GameError, StepResult,
+};
+
+/// 录像文件第一行,格式变了就改版本号
+const HEADER: &str = "r_snack replay 2";
+/// 键值部分和地图之间的分隔行
+const MAP_LINE: &str = "map";
+/// 输入前面的分隔行
+const INPUTS_LINE: &str = "inputs";
+
+impl<W: Write> Game<W> {
+    /// 开始记录每一帧的输入,重新开始一局时清空
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+    /// 把记录的输入连同种子、棋盘尺寸、速度、边界、开局的蛇和地图存到文件,
+    /// 没有开始记录时返回错误
+    pub fn save_replay<P: AsRef<Path>>(&self, path: P) -> Result<(), GameError> {
+        let inputs = self
+            .recording
+            .as_ref()
+            .ok_or_else(|| GameError::InvalidReplay("没有开始记录".to_string()))?;
+        let (width, height) = self.size();
+        let mut lines = vec![HEADER.to_string()];
+        lines.push(format!(
+            "seed {}",
+            self.seed.map_or("-".to_string(), |seed| seed.to_string())
+        ));
+        lines.push(format!("size {} {}", width, height));
+        lines.push(format!("speed {}", self.initial_speed));
+        lines.push(format!("border {}", border_to_string(self.border)));
+        lines.push(format!("snake {}", snack_to_string(&self.initial_snack)));
+        //自定义地图和场地才有地图部分,只记开局时的墙、刀刃和传送门
+        if let Some(walls) = &self.map_walls {
+            let portals = self
+                .portals
+                .iter()
+                .map(|&(a, b)| format!("{}>{}", pos_to_string(a), pos_to_string(b)))
+                .collect::<Vec<_>>();
+            lines.push(format!(
+                "portals {}",
+                if portals.is_empty() {
+                    "-".to_string()
+                } else {
+                    portals.join(" ")
+                }
+            ));
+            lines.push(MAP_LINE.to_string());
+            for y in 0..height {
+                let row = (0..width)
+                    .map(|x| {
+                        if (x, y) == self.initial_snack.head {
+                            'S'
+                        } else if walls.contains(&(x, y)) {
+                            '#'
+                        } else if self.map_blades.contains(&(x, y)) {
+                            'X'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>();
+                lines.push(row);
+            }
+        }
+        //每帧一个字符,`.`表示这一帧没有转向
+        let frames = inputs
+            .iter()
+            .map(|input| match input {
+                None => '.',
+                Some(Direction::Up) => 'U',
+                Some(Direction::Down) => 'D',
+                Some(Direction::Left) => 'L',
+                Some(Direction::Right) => 'R',
+            })
+            .collect::<String>();
+        lines.push(INPUTS_LINE.to_string());
+        lines.push(frames);
+        fs::write(path, lines.join("\n") + "\n")?;
+        Ok(())
+    }
+}
+impl Game {
+    /// 按录像里记的尺寸、速度、边界、开局的蛇和地图重建游戏后重放,返回重放完的游戏和最后一帧的结果。
+    /// 录像记了种子时seed必须相同,没记时用seed;其他配置(食物数量等)用默认值
+    pub fn replay<P: AsRef<Path>>(
+        path: P,
+        seed: u64,
+    ) -> Result<(Game<Sink>, StepResult), GameError> {
+        let text = fs::read_to_string(path)?;
+        let error = |reason: &str| GameError::InvalidReplay(reason.to_string());
+        let text = text
+            .strip_prefix(HEADER)
+            .ok_or_else(|| error("不是录像文件"))?;
+        let (head, frames) = text
+            .split_once(&format!("\n{}\n", INPUTS_LINE))
+            .ok_or_else(|| error("缺少输入"))?;
+        let (head, map) = match head.split_once(&format!("\n{}\n", MAP_LINE)) {
+            Some((head, map)) => (head, Some(map)),
+            None => (head, None),
+        };
+        let fields = head
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .collect::<HashMap<_, _>>();
+        let field = |key: &str| {
+            fields
+                .get(key)
+                .copied()
+                .ok_or_else(|| GameError::InvalidReplay(format!("缺少{}", key)))
+        };
+        match field("seed")? {
+            "-" => {}
+            recorded if recorded.parse() == Ok(seed) => {}
+            _ => return Err(error("种子和录像时的不同")),
+        }
+        let size = field("size")?
+            .split_once(' ')
+            .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+            .ok_or_else(|| error("size格式错误"))?;
+        let speed = field("speed")?
+            .parse()
+            .map_err(|_| error("speed格式错误"))?;
+        let border = parse_border(field("border")?).map_err(|_| error("border格式错误"))?;
+        let snack = parse_snack(field("snake")?).map_err(|_| error("snake格式错误"))?;
+        let inputs = frames
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .map(|c| match c {
+                '.' => Ok(None),
+                'U' => Ok(Some(Direction::Up)),
+                'D' => Ok(Some(Direction::Down)),
+                'L' => Ok(Some(Direction::Left)),
+                'R' => Ok(Some(Direction::Right)),
+                _ => Err(error("输入格式错误")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut config = GameConfig::new()
+            .size(size.0, size.1)
+            .speed(speed)
+            .border(border)
+            .seed(seed);
+        if let Some(map) = map {
+            config = config.map(map);
+        }
+        let mut game = config.build_with_writer(sink())?;
+        if map.is_some() {
+            game.portals = match field("portals")? {
+                "-" => Vec::new(),
+                portals => portals
+                    .split_whitespace()
+                    .map(|pair| {
+                        let (a, b) = pair.split_once('>')?;
+                        Some((parse_pos(a)?, parse_pos(b)?))
+                    })
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| error("portals格式错误"))?,
+            };
+        }
+        let (width, height) = game.size();
+        let out_of_bounds = std::iter::once(snack.head)
+            .chain(snack.bodys.iter().copied())
+            .chain(game.portals.iter().flat_map(|&(a, b)| [a, b]))
+            .any(|(x, y)| x >= width || y >= height);
+        if out_of_bounds {
+            return Err(error("坐标超出棋盘"));
+        }
+        game.initial_snack = snack.clone();
+        game.snack = snack;
+        game.check_layout()?;
+        game.build_default()?;
+        let result = game.simulate(inputs)?;
+        Ok((game, result))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 录一局再用同样的种子重放,分数、蛇和最后一帧的结果都要一样
+    fn assert_replays_the_same(config: GameConfig, name: &str) {
+        let mut game = config.seed(9).build_headless().unwrap();
+        game.start_recording();
+        //朝第一个食物直走,先对齐列再对齐行,吃到食物或者撞死为止都会录下来
+        let mut result = StepResult::Moved;
+        for _ in 0..400 {
+            let head = game.snack.head;
+            let food = game.food_positions()[0];
+            let input = if food.0 != head.0 {
+                Some(if food.0 < head.0 {
+                    Direction::Left
+                } else {
+                    Direction::Right
+                })
+            } else if food.1 < head.1 {
+                Some(Direction::Up)
+            } else {
+                Some(Direction::Down)
+            };
+            result = game.step(input).unwrap();
+            if !matches!(result, StepResult::Moved | StepResult::AteFood) {
+                break;
+            }
+        }
+        let path =
+            std::env::temp_dir().join(format!("r_snack_{}_{}.replay", name, std::process::id()));
+        game.save_replay(&path).unwrap();
+        let replayed = Game::replay(&path, 9);
+        let other_seed = Game::replay(&path, 10);
+        fs::remove_file(&path).unwrap();
+        let (replayed, replayed_result) = replayed.unwrap();
+        assert_eq!(replayed_result, result);
+        assert_eq!(replayed.score, game.score);
+        assert_eq!(replayed.ticks, game.ticks);
+        assert_eq!(
+            replayed.snake_cells().collect::<Vec<_>>(),
+            game.snake_cells().collect::<Vec<_>>()
+        );
+        assert_eq!(replayed.food_positions(), game.food_positions());
+        assert!(matches!(other_seed, Err(GameError::InvalidReplay(_))));
+    }
+
+    #[test]
+    fn replay_rebuilds_size_speed_and_wrap_from_the_file() {
+        let config = GameConfig::new()
+            .size(70, 24)
+            .speed(120)
+            .wrap(true)
+            .initial_snake((20, 12), 6, Direction::Right);
+        assert_replays_the_same(config, "wrap");
+    }
+
+    #[test]
+    fn replay_rebuilds_the_map_from_the_file() {
+        let map = (0..20)
+            .map(|y| {
+                (0..60)
+                    .map(|x| match (x, y) {
+                        (0 | 59, _) | (_, 0 | 19) => '#',
+                        (10..=20, 17) => '#',
+                        (40, 15) => 'X',
+                        (10, 10) | (50, 4) => '1',
+                        (5, 5) => 'S',
+                        _ => '.',
+                    })
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect::<String>();
+        assert_replays_the_same(GameConfig::new().map(&map), "map");
+    }
+}
@@ -0,0 +1,197 @@
+use std::io::Write;
+
+use crate::{
+    border::Edge, validate_dimensions, BorderMode, Cell, CellType, Game, GameError, Snack,
+};
+
+impl<W: Write> Game<W> {
+    /// 运行中改变棋盘尺寸,分数和蛇保留,蛇出了新的边界就整条平移进来,怎么移都放不下时报错,棋盘不变。
+    /// 食物重新放,新棋盘以内的障碍墙和刀刃保留,改完重画整个画面。
+    /// 自定义地图、关卡、特殊场地和双人模式的墙是按尺寸定好的,不能改尺寸
+    pub fn resize(&mut self, width: usize, height: usize) -> Result<(), GameError> {
+        validate_dimensions(width, height, None)?;
+        if self.map_walls.is_some() || self.rival.is_some() {
+            return Err(GameError::InvalidMap(
+                "自定义地图、场地和双人模式不能改变尺寸".to_string(),
+            ));
+        }
+        let size = (width, height);
+        let snack = fit_snack(&self.snack, self.border, size).ok_or(GameError::SnakeOutOfBounds)?;
+        //开局的蛇放不进新棋盘就换成默认的,重新开始时用
+        let initial = fit_snack(&self.initial_snack, self.border, size).unwrap_or_else(Snack::new);
+        let (old_w, old_h) = self.size();
+        let hazards = (0..old_w.min(width))
+            .flat_map(|x| (0..old_h.min(height)).map(move |y| (x, y)))
+            .filter(|&(x, y)| {
+                self.cells[x][y].cell_type == CellType::Wall
+                    && !self.border.is_wall((x, y), (old_w, old_h), 1)
+                    && !self.border.is_wall((x, y), size, 1)
+            })
+            .collect::<Vec<_>>();
+        self.snack = snack;
+        self.initial_snack = initial;
+        self.cells = new_cells(width, height);
+        self.dirty.clear();
+        self.hud.clear();
+        self.foods.clear();
+        self.food_points.clear();
+        self.food_distances.clear();
+        self.bonus = None;
+        self.poison = None;
+        self.runner = None;
+        self.trail.clear();
+        self.grace_wall = None;
+        self.wrap_flash = None;
+        self.last_wrap = None;
+        self.warned_wall = None;
+        self.move_hint = None;
+        self.tail_cell = None;
+        self.reset_shrink();
+        //刀刃和障碍墙一样,出了新棋盘或落在新的边界墙上就去掉
+        let border = self.border;
+        self.map_blades
+            .retain(|&(x, y)| x < width && y < height && !border.is_wall((x, y), size, 1));
+        let snake = self.snake_cells().collect::<Vec<_>>();
+        for (x, y) in hazards {
+            if !snake.contains(&(x, y)) {
+                self.cells[x][y].cell_type = CellType::Wall;
+            }
+        }
+        self.build_default()?;
+        if self.in_alt_screen {
+            self.request_full_redraw()?;
+        }
+        Ok(())
+    }
+}
+/// 空的格子
+pub(crate) fn new_cells(width: usize, height: usize) -> Vec<Vec<Cell>> {
+    (0..width)
+        .map(|x| {
+            (0..height)
+                .map(|y| Cell {
+                    x,
+                    y,
+                    changed_flag: false,
+                    cell_type: CellType::Empty,
+                    faded: false,
+                    highlighted: false,
+                    shade: 0,
+                    tail: false,
+                    expiring: false,
+                    rendered: None,
+                })
+                .collect()
+        })
+        .collect()
+}
+/// 把蛇平移到新棋盘的墙以内,本来就在里面的不动,太长放不下返回None
+fn fit_snack(snack: &Snack, border: BorderMode, (width, height): (usize, usize)) -> Option<Snack> {
+    let cells = std::iter::once(snack.head)
+        .chain(snack.bodys.iter().copied())
+        .collect::<Vec<_>>();
+    let d_x = shift_into(
+        cells.iter().map(|&(x, _)| x),
+        usize::from(border.left == Edge::Wall),
+        width - 1 - usize::from(border.right == Edge::Wall),
+    )?;
+    let d_y = shift_into(
+        cells.iter().map(|&(_, y)| y),
+        usize::from(border.top == Edge::Wall),
+        height - 1 - usize::from(border.bottom == Edge::Wall),
+    )?;
+    let shift = |(x, y): (usize, usize)| {
+        (
+            x.checked_add_signed(d_x).unwrap_or(x),
+            y.checked_add_signed(d_y).unwrap_or(y),
+        )
+    };
+    Some(Snack {
+        head: shift(snack.head),
+        bodys: snack.bodys.iter().map(|&pos| shift(pos)).collect(),
+        ..snack.clone()
+    })
+}
+/// 坐标整体移进low..=high要移动多少,范围不够宽时返回None
+fn shift_into<I: Iterator<Item = usize> + Clone>(
+    coords: I,
+    low: usize,
+    high: usize,
+) -> Option<isize> {
+    let min = coords.clone().min()?;
+    let max = coords.max()?;
+    if max - min > high - low {
+        None
+    } else if min < low {
+        Some((low - min) as isize)
+    } else if max > high {
+        Some(-((max - high) as isize))
+    } else {
+        Some(0)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::{CellType, Direction, GameConfig, GameError};
+
+    #[test]
+    fn growing_keeps_snake_and_score_and_redraws() {
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .seed(1)
+            .build_with_writer(Vec::new())
+            .unwrap();
+        game.build_default().unwrap();
+        game.score = 7;
+        let snake = game.snake_cells().collect::<Vec<_>>();
+        game.in_alt_screen = true;
+        game.resize(100, 30).unwrap();
+        assert_eq!(game.size(), (100, 30));
+        assert_eq!(game.score, 7);
+        assert_eq!(game.snake_cells().collect::<Vec<_>>(), snake);
+        assert_eq!(game.cell_type(99, 29), Some(CellType::Wall));
+        //清屏后整个画面重画
+        let output = String::from_utf8_lossy(&game.writer);
+        assert!(output.contains("\x1b[2J"));
+    }
+
+    #[test]
+    fn shrinking_moves_the_snake_inside_or_fails() {
+        let mut game = GameConfig::new()
+            .size(100, 30)
+            .seed(1)
+            .initial_snake((90, 25), 10, Direction::Right)
+            .build_headless()
+            .unwrap();
+        game.resize(60, 20).unwrap();
+        //整条往左上平移到墙里面,形状不变
+        let snake = game.snake_cells().collect::<Vec<_>>();
+        assert_eq!(snake.first(), Some(&(58, 18)));
+        assert_eq!(snake.last(), Some(&(49, 18)));
+        assert_eq!(game.cell_type(58, 18), Some(CellType::SnackHead));
+        //比最小尺寸还小
+        assert!(matches!(
+            game.resize(59, 20),
+            Err(GameError::BoardTooSmall { .. })
+        ));
+        assert_eq!(game.size(), (60, 20));
+    }
+
+    #[test]
+    fn shrinking_below_the_snake_length_fails_and_keeps_the_board() {
+        let mut game = GameConfig::new()
+            .size(100, 30)
+            .seed(1)
+            .initial_snake((80, 10), 70, Direction::Right)
+            .build_headless()
+            .unwrap();
+        assert!(matches!(
+            game.resize(70, 20),
+            Err(GameError::SnakeOutOfBounds)
+        ));
+        assert_eq!(game.size(), (100, 30));
+        assert_eq!(game.snake_len(), 70);
+        game.resize(72, 20).unwrap();
+        assert_eq!(game.snake_len(), 70);
+    }
+}
@@ -0,0 +1,373 @@
+use std::{collections::VecDeque, io::Write};
+
+use crate::{
+    death_cause, push_direction, CellType, Direction, Game, GameError, GameEvent, Snack,
+    StepResult, BONUS_SCORE, POISON_PENALTY, POISON_SEGMENTS,
+};
+
+/// 双人模式的第二条蛇,用方向键控制
+#[derive(Clone)]
+pub(crate) struct Rival {
+    pub snack: Snack,
+    /// 开局时的蛇,重新开始时恢复
+    pub initial: Snack,
+    pub score: usize,
+    pub alive: bool,
+    pub input_queue: VecDeque<(Direction, u64)>,
+}
+/// 双人模式蛇头撞到另一条蛇的蛇身时谁死
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BodyHit {
+    /// 撞人的一方死
+    AttackerDies,
+    /// 撞人的一方比对方长,被撞的一方死
+    VictimDies,
+}
+impl BodyHit {
+    /// 长度为attacker的蛇头撞到长度为victim的蛇身时的结果。bigger_eats_smaller是大吃小规则的开关,
+    /// 开启时比对方长就是对方死,不比对方长还是撞人的死;关闭时谁撞上谁死
+    pub fn resolve(bigger_eats_smaller: bool, attacker: usize, victim: usize) -> Self {
+        if bigger_eats_smaller && attacker > victim {
+            BodyHit::VictimDies
+        } else {
+            BodyHit::AttackerDies
+        }
+    }
+}
+/// 双人模式两个蛇头同一帧走进同一格时的规则
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum HeadOn {
+    /// 两条蛇都死
+    #[default]
+    BothDie,
+    /// 长的一方活下来,一样长都死
+    LongerSurvives,
+}
+impl HeadOn {
+    /// 长度为len和other_len的两条蛇迎头相撞后各自还活不活,先于撞身体的判断处理
+    pub fn survivors(self, len: usize, other_len: usize) -> (bool, bool) {
+        match self {
+            HeadOn::BothDie => (false, false),
+            HeadOn::LongerSurvives => (len > other_len, other_len > len),
+        }
+    }
+}
+/// 双人模式的结果
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Winner {
+    PlayerOne,
+    PlayerTwo,
+    /// 同时死亡且分数相同
+    Draw,
+}
+impl Rival {
+    /// 放在棋盘右侧朝左,和玩家一的默认位置左右对称
+    pub fn new((width, height): (usize, usize)) -> Result<Self, GameError> {
+        let snack = Snack::straight(
+            (width - 10, height - 8),
+            8,
+            Direction::Left,
+            (width, height),
+        )?;
+        Ok(Rival {
+            initial: snack.clone(),
+            snack,
+            score: 0,
+            alive: true,
+            input_queue: VecDeque::new(),
+        })
+    }
+    /// 恢复到开局状态
+    pub fn reset(&mut self) {
+        self.snack = self.initial.clone();
+        self.score = 0;
+        self.alive = true;
+        self.input_queue.clear();
+    }
+}
+impl<W: Write> Game<W> {
+    /// 第二条蛇走一步,撞到墙或任何一条蛇就死,这一帧死了返回true
+    pub(crate) fn step_rival(&mut self, input: Option<Direction>) -> Result<bool, GameError> {
+        let Some(mut rival) = self.rival.take() else {
+            return Ok(false);
+        };
+        if !rival.alive {
+            self.rival = Some(rival);
+            return Ok(false);
+        }
+        if let Some(dir) = input {
+            if dir != rival.snack.moved.opposite() {
+                rival.snack.direction = dir;
+            }
+        }
+        let next = self
+            .destination(rival.snack.head, rival.snack.direction)
+            .map(|(x, y)| (self.cells[x][y].cell_type, (x, y)));
+        let scored = matches!(
+            next,
+            Some((
+                CellType::Food | CellType::BonusFood | CellType::Poison | CellType::Runner,
+                _
+            ))
+        );
+        let result = match next {
+            Some((CellType::Food, (x, y))) => {
+                let value = self.food_value((x, y));
+                self.remove_food((x, y));
+                self.grow_rival(&mut rival.snack, x, y);
+                rival.score += value;
+                self.fill_food();
+                Ok(false)
+            }
+            Some((CellType::BonusFood, (x, y))) => {
+                self.bonus = None;
+                self.grow_rival(&mut rival.snack, x, y);
+                rival.score += BONUS_SCORE;
+                Ok(false)
+            }
+            Some((CellType::Runner, (x, y))) => {
+                self.runner = None;
+                self.grow_rival(&mut rival.snack, x, y);
+                rival.score += BONUS_SCORE;
+                Ok(false)
+            }
+            Some((CellType::Poison, (x, y))) => {
+                self.poison = None;
+                self.grow_rival(&mut rival.snack, x, y);
+                for _ in 0..=POISON_SEGMENTS {
+                    match rival.snack.bodys.pop_back() {
+                        Some((x, y)) => self.set_cell(x, y, CellType::Empty),
+                        None => break,
+                    }
+                }
+                rival.score = rival.score.saturating_sub(POISON_PENALTY);
+                Ok(false)
+            }
+            Some((CellType::Empty, (x, y))) => {
+                self.grow_rival(&mut rival.snack, x, y);
+                if let Some((x, y)) = rival.snack.bodys.pop_back() {
+                    self.set_cell(x, y, CellType::Empty);
+                }
+                Ok(false)
+            }
+            //大吃小:比玩家一长就是玩家一死
+            Some((CellType::SnackBody, _))
+                if BodyHit::resolve(
+                    self.bigger_eats_smaller,
+                    rival.snack.bodys.len(),
+                    self.snack.bodys.len(),
+                ) == BodyHit::VictimDies =>
+            {
+                self.lives = 0;
+                self.emit(GameEvent::Died(StepResult::HitRival));
+                Ok(false)
+            }
+            _ => {
+                rival.alive = false;
+                Ok(true)
+            }
+        };
+        self.rival = Some(rival);
+        if scored {
+            self.print_score()?;
+        }
+        result
+    }
+    /// 两个蛇头这一帧要走进同一格时按`head_on`规则决定谁死,活下来的照常走一步;
+    /// 不会相撞时返回None,由`step`和`step_rival`各自处理
+    pub(crate) fn resolve_head_on(
+        &mut self,
+        input: Option<Direction>,
+        rival_input: Option<Direction>,
+    ) -> Result<Option<StepResult>, GameError> {
+        let Some(rival) = self.rival.as_ref().filter(|rival| rival.alive) else {
+            return Ok(None);
+        };
+        let turned = |snack: &Snack, input: Option<Direction>| {
+            input
+                .filter(|&dir| dir != snack.moved.opposite())
+                .unwrap_or(snack.direction)
+        };
+        //走进传送门的从出口出来,按最终到达的格子比
+        let player_dir = turned(&self.snack, input.map(|dir| self.controlled(dir)));
+        let target = self.destination(self.snack.head, player_dir);
+        let rival_target = self.destination(rival.snack.head, turned(&rival.snack, rival_input));
+        let Some((x, y)) = target.filter(|&target| Some(target) == rival_target) else {
+            return Ok(None);
+        };
+        //一起撞墙各自处理
+        if self.cells[x][y].cell_type == CellType::Wall {
+            return Ok(None);
+        }
+        let (survives, rival_survives) = self
+            .head_on
+            .survivors(self.snack.bodys.len(), rival.snack.bodys.len());
+        if survives {
+            if let Some(rival) = self.rival.as_mut() {
+                rival.alive = false;
+            }
+            let result = self.step(input)?;
+            return Ok(Some(match death_cause(result) {
+                Some(_) => result,
+                None => StepResult::RivalDied,
+            }));
+        }
+        let result = self.die(StepResult::HitRival)?;
+        if rival_survives {
+            self.step_rival(rival_input)?;
+        } else if let Some(rival) = self.rival.as_mut() {
+            rival.alive = false;
+        }
+        Ok(Some(result))
+    }
+    /// 大吃小规则下玩家一撞到第二条蛇的蛇身时能不能吃掉它
+    pub(crate) fn eats_rival(&self) -> bool {
+        self.rival.as_ref().is_some_and(|rival| {
+            BodyHit::resolve(
+                self.bigger_eats_smaller,
+                self.snack.bodys.len(),
+                rival.snack.bodys.len(),
+            ) == BodyHit::VictimDies
+        })
+    }
+    /// 第二条蛇的蛇头前进到(x, y),尾巴不动
+    fn grow_rival(&mut self, snack: &mut Snack, x: usize, y: usize) {
+        let (h_x, h_y) = snack.head;
+        snack.bodys.push_front((h_x, h_y));
+        self.set_cell(h_x, h_y, CellType::Snack2Body);
+        snack.head = (x, y);
+        snack.moved = snack.direction;
+        self.set_cell(x, y, CellType::Snack2Head);
+    }
+    /// 第二条蛇的方向加入它的输入缓冲
+    pub(crate) fn queue_rival_direction(&mut self, dir: Direction) {
+        if let Some(rival) = self.rival.as_mut() {
+            push_direction(&mut rival.input_queue, dir, self.ticks);
+        }
+    }
+    /// 双人模式中玩家二的分数,单人模式返回None
+    pub fn rival_score(&self) -> Option<usize> {
+        self.rival.as_ref().map(|rival| rival.score)
+    }
+    /// 双人模式的胜负,还没分出来或单人模式返回None
+    pub fn winner(&self) -> Option<Winner> {
+        let rival = self.rival.as_ref()?;
+        match (self.lives > 0, rival.alive) {
+            (true, true) => None,
+            (true, false) => Some(Winner::PlayerOne),
+            (false, true) => Some(Winner::PlayerTwo),
+            (false, false) => Some(match self.score.cmp(&rival.score) {
+                std::cmp::Ordering::Greater => Winner::PlayerOne,
+                std::cmp::Ordering::Less => Winner::PlayerTwo,
+                std::cmp::Ordering::Equal => Winner::Draw,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GameConfig, GameOutcome};
+
+    /// 玩家一在(46, 12)朝右,第二条蛇在(50, 12)朝左,走两帧蛇头撞进(48, 12)
+    fn head_on_game(length: usize, rule: HeadOn) -> Game<std::io::Sink> {
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .seed(1)
+            .two_player(true)
+            .head_on(rule)
+            .initial_snake((46, 12), length, Direction::Right)
+            .build_headless()
+            .unwrap();
+        assert_eq!(game.step_two(None, None).unwrap(), StepResult::Moved);
+        game
+    }
+
+    #[test]
+    fn head_on_kills_both() {
+        assert_eq!(HeadOn::BothDie.survivors(12, 3), (false, false));
+        let mut game = head_on_game(3, HeadOn::BothDie);
+        assert_eq!(game.step_two(None, None).unwrap(), StepResult::HitRival);
+        assert_eq!(game.lives, 0);
+        assert!(!game.rival.as_ref().unwrap().alive);
+        //两条蛇都要闪
+        let both = game.snack.bodys.len() + 1 + game.rival.as_ref().unwrap().snack.bodys.len() + 1;
+        let cells = game.dead_snake_cells(GameOutcome::TwoPlayerOver(Winner::Draw));
+        assert_eq!(cells.len(), both);
+    }
+
+    #[test]
+    fn paused_game_skips_head_on() {
+        let mut game = head_on_game(3, HeadOn::BothDie);
+        game.pause().unwrap();
+        assert_eq!(game.step_two(None, None).unwrap(), StepResult::Paused);
+        assert!(game.lives > 0);
+        assert!(game.rival.as_ref().unwrap().alive);
+    }
+
+    #[test]
+    fn head_on_longer_survives() {
+        assert_eq!(HeadOn::LongerSurvives.survivors(12, 3), (true, false));
+        assert_eq!(HeadOn::LongerSurvives.survivors(3, 12), (false, true));
+        //一样长都死
+        assert_eq!(HeadOn::LongerSurvives.survivors(5, 5), (false, false));
+    }
+
+    #[test]
+    fn head_on_longer_player_survives() {
+        let mut game = head_on_game(12, HeadOn::LongerSurvives);
+        assert_eq!(game.step_two(None, None).unwrap(), StepResult::RivalDied);
+        assert_eq!(game.snack.head, (48, 12));
+        assert_eq!(game.winner(), Some(Winner::PlayerOne));
+    }
+
+    #[test]
+    fn head_on_longer_rival_survives() {
+        let mut game = head_on_game(3, HeadOn::LongerSurvives);
+        assert_eq!(game.step_two(None, None).unwrap(), StepResult::HitRival);
+        let rival = game.rival.as_ref().unwrap();
+        assert!(rival.alive);
+        assert_eq!(rival.snack.head, (48, 12));
+        assert_eq!(game.winner(), Some(Winner::PlayerTwo));
+    }
+
+    #[test]
+    fn inverted_turn_counts_for_head_on() {
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .seed(1)
+            .two_player(true)
+            .invert_controls(true)
+            .initial_snake((47, 11), 3, Direction::Right)
+            .build_headless()
+            .unwrap();
+        assert_eq!(game.step_two(None, None).unwrap(), StepResult::Moved);
+        //反向操作时按上是往下走,正好和第二条蛇撞进(48, 12)
+        assert_eq!(
+            game.step_two(Some(Direction::Up), None).unwrap(),
+            StepResult::HitRival
+        );
+        assert!(!game.rival.as_ref().unwrap().alive);
+    }
+
+    #[test]
+    fn bigger_snake_eats_the_smaller_body() {
+        //玩家一从上往下撞进第二条蛇的蛇身,第三帧撞上
+        let crash = |bigger_eats_smaller| {
+            let mut game = GameConfig::new()
+                .size(60, 40)
+                .seed(1)
+                .two_player(true)
+                .bigger_eats_smaller(bigger_eats_smaller)
+                .initial_snake((54, 29), 20, Direction::Down)
+                .build_headless()
+                .unwrap();
+            game.step_two(None, None).unwrap();
+            game.step_two(None, None).unwrap();
+            game.step_two(None, None).unwrap()
+        };
+        assert_eq!(crash(false), StepResult::HitRival);
+        assert_eq!(crash(true), StepResult::RivalDied);
+    }
+}
@@ -0,0 +1,61 @@
+use std::io::Write;
+
+use rand::Rng;
+
+use crate::{CellType, Direction, Game, GameError, GameEvent, BONUS_SCORE};
+
+impl<W: Write> Game<W> {
+    /// 开启会跑的食物时,棋盘上没有就随机放一个,没有空位就算了
+    pub(crate) fn spawn_runner(&mut self) {
+        if self.runner_every.is_none() || self.runner.is_some() {
+            return;
+        }
+        if let Some((x, y)) = self.random_empty_cell() {
+            self.set_cell(x, y, CellType::Runner);
+            self.runner = Some((x, y));
+        }
+    }
+    /// 每隔几帧往随机一个相邻的空格子挪一格,不会挪到墙、蛇和其他食物上,四周都没空就不动
+    pub(crate) fn move_runner(&mut self) {
+        let (Some(every), Some(pos)) = (self.runner_every, self.runner) else {
+            return;
+        };
+        if !self.ticks.is_multiple_of(every) {
+            return;
+        }
+        //最多四个方向,放在数组里不分配内存
+        let mut choices = [(0, 0); 4];
+        let mut count = 0;
+        for dir in [
+            Direction::Left,
+            Direction::Right,
+            Direction::Up,
+            Direction::Down,
+        ] {
+            if let Some((x, y)) = self.next_position_from(pos, dir) {
+                if self.cells[x][y].cell_type == CellType::Empty {
+                    choices[count] = (x, y);
+                    count += 1;
+                }
+            }
+        }
+        if count == 0 {
+            return;
+        }
+        let (x, y) = choices[self.rng.gen_range(0..count)];
+        self.set_cell(pos.0, pos.1, CellType::Empty);
+        self.set_cell(x, y, CellType::Runner);
+        self.runner = Some((x, y));
+    }
+    /// 吃到会跑的食物,和奖励食物一样加分
+    pub(crate) fn eat_runner(&mut self, x: usize, y: usize) -> Result<(), GameError> {
+        self.runner = None;
+        self.grow(x, y);
+        self.score += BONUS_SCORE;
+        self.stats.food_eaten += 1;
+        self.session.food_eaten += 1;
+        self.emit(GameEvent::FoodEaten { score: self.score });
+        self.print_score()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,685 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
+use crate::{BorderMode, CellType, Direction, Edge, Game, GameConfig, GameError, Snack, Stats};
+
+/// 存档第一行,格式变了就改版本号
+const HEADER: &str = "r_snack save 8";
+/// 键值部分和地图之间的分隔行
+const MAP_LINE: &str = "map";
+
+impl<W: Write> Game<W> {
+    /// 把当前局面连同随机数状态存到文件,双人和关卡模式存不下来,返回错误。
+    /// 先写到旁边的临时文件再改名,写到一半出问题也不会弄坏原来的存档
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), GameError> {
+        if let Some(mode) = self.unsaveable_mode() {
+            return Err(GameError::SaveUnsupported(mode));
+        }
+        let path = path.as_ref();
+        let mut tmp = OsString::from(path.as_os_str());
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        fs::write(&tmp, self.save_string())?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+    /// 开启自动存档时,离上次存档过了设定的时间就存一次
+    pub(crate) fn check_autosave(&mut self, last: &mut Instant) -> Result<(), GameError> {
+        let Some((path, every)) = &self.autosave else {
+            return Ok(());
+        };
+        if last.elapsed() >= *every {
+            self.save(path)?;
+            *last = Instant::now();
+        }
+        Ok(())
+    }
+    /// 存档不支持的模式:第二条蛇和后面的关卡都不存
+    pub(crate) fn unsaveable_mode(&self) -> Option<&'static str> {
+        if self.rival.is_some() {
+            Some("双人模式")
+        } else if !self.levels.is_empty() {
+            Some("关卡模式")
+        } else {
+            None
+        }
+    }
+    fn save_string(&self) -> String {
+        let mut lines = vec![HEADER.to_string()];
+        lines.push(format!("score {}", self.score));
+        lines.push(format!("speed {} {}", self.speed, self.initial_speed));
+        lines.push(format!("ticks {}", self.ticks));
+        lines.push(format!("lives {} {}", self.lives, self.initial_lives));
+        lines.push(format!("border {}", border_to_string(self.border)));
+        lines.push(format!(
+            "seed {}",
+            self.seed.map_or("-".to_string(), |seed| seed.to_string())
+        ));
+        //种子、流编号和已经用掉的位置,读档后接着生成同样的随机数
+        lines.push(format!(
+            "rng {} {} {}",
+            self.rng
+                .get_seed()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>(),
+            self.rng.get_stream(),
+            self.rng.get_word_pos()
+        ));
+        lines.push(format!("wrap_cost {}", self.wrap_cost));
+        lines.push(format!(
+            "target_score {}",
+            self.target_score
+                .map_or("-".to_string(), |score| score.to_string())
+        ));
+        lines.push(format!(
+            "time_limit {}",
+            self.time_limit
+                .map_or("-".to_string(), |limit| limit.as_millis().to_string())
+        ));
+        lines.push(format!(
+            "food_lifetime {}",
+            self.food_lifetime
+                .map_or("-".to_string(), |(frames, penalty)| format!(
+                    "{} {}",
+                    frames, penalty
+                ))
+        ));
+        lines.push(format!(
+            "food_values {}",
+            self.food_values
+                .map_or("-".to_string(), |(min, max)| format!("{} {}", min, max))
+        ));
+        //每个食物的分值是`x,y,分值`
+        let mut points = self
+            .food_points
+            .iter()
+            .map(|(&(x, y), value)| format!("{},{},{}", x, y, value))
+            .collect::<Vec<_>>();
+        points.sort();
+        lines.push(format!(
+            "food_points {}",
+            if points.is_empty() {
+                "-".to_string()
+            } else {
+                points.join(" ")
+            }
+        ));
+        //每对传送门是`x,y>x,y`,数量不受地图里一位数字的限制
+        let portals = self
+            .portals
+            .iter()
+            .map(|&(a, b)| format!("{}>{}", pos_to_string(a), pos_to_string(b)))
+            .collect::<Vec<_>>();
+        lines.push(format!(
+            "portals {}",
+            if portals.is_empty() {
+                "-".to_string()
+            } else {
+                portals.join(" ")
+            }
+        ));
+        lines.push(format!("snake {}", snack_to_string(&self.snack)));
+        lines.push(format!(
+            "initial_snake {}",
+            snack_to_string(&self.initial_snack)
+        ));
+        //每个食物是`x,y,生成时的帧数`
+        let foods = self
+            .foods
+            .iter()
+            .map(|&((x, y), spawn_tick)| format!("{},{},{}", x, y, spawn_tick))
+            .collect::<Vec<_>>();
+        lines.push(format!("food_count {}", self.food_count));
+        lines.push(format!(
+            "growth {} {}",
+            self.growth_per_food,
+            //推迟的生长存档后不再推迟
+            self.pending_growth
+                + self
+                    .delayed_growth
+                    .iter()
+                    .map(|&(_, growth)| growth)
+                    .sum::<usize>()
+        ));
+        lines.push(format!(
+            "food {}",
+            if foods.is_empty() {
+                "-".to_string()
+            } else {
+                foods.join(" ")
+            }
+        ));
+        lines.push(format!(
+            "bonus {}",
+            self.bonus.map_or("-".to_string(), |(pos, frames)| format!(
+                "{} {}",
+                pos_to_string(pos),
+                frames
+            ))
+        ));
+        lines.push(format!(
+            "poison {}",
+            self.poison.map_or("-".to_string(), pos_to_string)
+        ));
+        lines.push(format!(
+            "runner {}",
+            self.runner.map_or("-".to_string(), pos_to_string)
+        ));
+        lines.push(format!(
+            "combo {} {}",
+            self.combo,
+            self.last_eat_tick
+                .map_or("-".to_string(), |tick| tick.to_string())
+        ));
+        lines.push(format!(
+            "invuln {} {}",
+            self.invuln_frames, self.self_overlap
+        ));
+        lines.push(format!(
+            "hazard_every {}",
+            self.hazard_every
+                .map_or("-".to_string(), |every| every.to_string())
+        ));
+        lines.push(format!("next_hazard {}", self.next_hazard));
+        lines.push(format!(
+            "shrink_every {}",
+            self.shrink_every
+                .map_or("-".to_string(), |every| every.as_millis().to_string())
+        ));
+        lines.push(format!(
+            "shrink {} {}",
+            self.next_shrink.as_millis(),
+            self.shrink_depth
+        ));
+        //等蛇走开再变成墙的格子
+        let pending = self
+            .shrink_pending
+            .iter()
+            .map(|&pos| pos_to_string(pos))
+            .collect::<Vec<_>>();
+        lines.push(format!(
+            "shrink_pending {}",
+            if pending.is_empty() {
+                "-".to_string()
+            } else {
+                pending.join(" ")
+            }
+        ));
+        lines.push(format!(
+            "stats {} {} {} {}",
+            self.stats.food_eaten,
+            self.stats.turns,
+            self.stats.frames,
+            self.stats.play_time.as_millis()
+        ));
+        //地图只记录墙、刀刃和蛇头,其他从键值部分恢复
+        lines.push(MAP_LINE.to_string());
+        let (width, height) = self.size();
+        for y in 0..height {
+            let row = (0..width)
+                .map(|x| {
+                    if (x, y) == self.snack.head {
+                        'S'
+                    } else if self.cells[x][y].cell_type == CellType::Wall {
+                        '#'
+                    } else if self.cells[x][y].cell_type == CellType::Blade {
+                        'X'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            lines.push(row);
+        }
+        lines.join("\n") + "\n"
+    }
+}
+impl Game {
+    /// 读取存档,恢复成可以继续玩的游戏,其他设置用默认配置,见`GameConfig::load`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, GameError> {
+        GameConfig::new().load(path)
+    }
+}
+/// 读取存档,地图、速度、命数等存档里有的内容覆盖`config`,主题和按键等用`config`的,
+/// `build`按最后的配置创建游戏
+pub(crate) fn load_save<W: Write>(
+    config: GameConfig,
+    path: &Path,
+    build: impl FnOnce(GameConfig) -> Result<Game<W>, GameError>,
+) -> Result<Game<W>, GameError> {
+    let text = fs::read_to_string(path)?;
+    let (fields, map) = split_save(&text)?;
+    let field = |key: &str| {
+        fields
+            .get(key)
+            .copied()
+            .ok_or_else(|| GameError::InvalidSave(format!("缺少{}", key)))
+    };
+    let speed = numbers(field("speed")?, "speed")?;
+    let lives = numbers(field("lives")?, "lives")?;
+    let (Some(&speed), Some(&initial_speed), Some(&lives), Some(&initial_lives)) =
+        (speed.first(), speed.get(1), lives.first(), lives.get(1))
+    else {
+        return Err(GameError::InvalidSave("速度或命数不完整".to_string()));
+    };
+    let mut config = config
+        .map(map)
+        .speed(initial_speed)
+        .lives(initial_lives as usize)
+        .border(parse_border(field("border")?)?);
+    if let Some(seed) = optional(field("seed")?, |seed| seed.parse().ok(), "seed")? {
+        config = config.seed(seed);
+    }
+    let mut game = build(config)?;
+    game.rng = parse_rng(field("rng")?)?;
+    game.wrap_cost = parse(field("wrap_cost")?, "wrap_cost")?;
+    game.target_score = optional(
+        field("target_score")?,
+        |score| score.parse().ok(),
+        "target_score",
+    )?;
+    game.time_limit = optional(
+        field("time_limit")?,
+        |ms| ms.parse().ok().map(Duration::from_millis),
+        "time_limit",
+    )?;
+    game.food_lifetime = optional(
+        field("food_lifetime")?,
+        |value| {
+            let (frames, penalty) = value.split_once(' ')?;
+            Some((frames.parse().ok()?, penalty.parse().ok()?))
+        },
+        "food_lifetime",
+    )?;
+    game.food_values = optional(
+        field("food_values")?,
+        |value| {
+            let (min, max) = value.split_once(' ')?;
+            Some((min.parse().ok()?, max.parse().ok()?))
+        },
+        "food_values",
+    )?;
+    game.food_points = optional(
+        field("food_points")?,
+        |points| {
+            points
+                .split_whitespace()
+                .map(|point| {
+                    let (pos, value) = point.rsplit_once(',')?;
+                    Some((parse_pos(pos)?, value.parse().ok()?))
+                })
+                .collect()
+        },
+        "food_points",
+    )?
+    .unwrap_or_default();
+    game.portals = optional(
+        field("portals")?,
+        |portals| {
+            portals
+                .split_whitespace()
+                .map(|pair| {
+                    let (a, b) = pair.split_once('>')?;
+                    Some((parse_pos(a)?, parse_pos(b)?))
+                })
+                .collect()
+        },
+        "portals",
+    )?
+    .unwrap_or_default();
+    game.speed = speed;
+    game.lives = lives as usize;
+    game.score = parse(field("score")?, "score")?;
+    game.ticks = parse(field("ticks")?, "ticks")?;
+    game.snack = parse_snack(field("snake")?)?;
+    game.initial_snack = parse_snack(field("initial_snake")?)?;
+    game.foods = optional(
+        field("food")?,
+        |foods| {
+            foods
+                .split_whitespace()
+                .map(|food| {
+                    let (pos, spawn_tick) = food.rsplit_once(',')?;
+                    Some((parse_pos(pos)?, spawn_tick.parse().ok()?))
+                })
+                .collect()
+        },
+        "food",
+    )?
+    .unwrap_or_default();
+    game.food_count = parse(field("food_count")?, "food_count")?;
+    let [growth_per_food, pending_growth] = numbers(field("growth")?, "growth")?[..] else {
+        return Err(GameError::InvalidSave("growth格式错误".to_string()));
+    };
+    game.growth_per_food = growth_per_food.max(1) as usize;
+    game.pending_growth = pending_growth as usize;
+    game.bonus = optional(
+        field("bonus")?,
+        |bonus| {
+            let (pos, frames) = bonus.split_once(' ')?;
+            Some((parse_pos(pos)?, frames.parse().ok()?))
+        },
+        "bonus",
+    )?;
+    game.poison = optional(field("poison")?, parse_pos, "poison")?;
+    game.runner = optional(field("runner")?, parse_pos, "runner")?;
+    let stats = numbers(field("stats")?, "stats")?;
+    let [food_eaten, turns, frames, play_ms] = stats[..] else {
+        return Err(GameError::InvalidSave("stats格式错误".to_string()));
+    };
+    game.stats = Stats {
+        food_eaten: food_eaten as usize,
+        turns: turns as usize,
+        frames,
+        play_time: Duration::from_millis(play_ms),
+    };
+    let (combo, last_eat_tick) = field("combo")?
+        .split_once(' ')
+        .ok_or_else(|| GameError::InvalidSave("combo格式错误".to_string()))?;
+    game.combo = parse::<usize>(combo, "combo")?.max(1);
+    game.last_eat_tick = optional(last_eat_tick, |tick| tick.parse().ok(), "combo")?;
+    let [invuln_frames, self_overlap] = numbers(field("invuln")?, "invuln")?[..] else {
+        return Err(GameError::InvalidSave("invuln格式错误".to_string()));
+    };
+    game.invuln_frames = invuln_frames;
+    game.self_overlap = self_overlap;
+    game.hazard_every = optional(
+        field("hazard_every")?,
+        |every| every.parse().ok().filter(|&every| every > 0),
+        "hazard_every",
+    )?;
+    game.next_hazard = parse(field("next_hazard")?, "next_hazard")?;
+    game.shrink_every = optional(
+        field("shrink_every")?,
+        |ms| ms.parse().ok().map(Duration::from_millis),
+        "shrink_every",
+    )?;
+    let [next_shrink, shrink_depth] = numbers(field("shrink")?, "shrink")?[..] else {
+        return Err(GameError::InvalidSave("shrink格式错误".to_string()));
+    };
+    game.next_shrink = Duration::from_millis(next_shrink);
+    game.shrink_depth = shrink_depth as usize;
+    game.shrink_pending = optional(
+        field("shrink_pending")?,
+        |cells| cells.split_whitespace().map(parse_pos).collect(),
+        "shrink_pending",
+    )?
+    .unwrap_or_default();
+    game.restore_cells()?;
+    Ok(game)
+}
+impl<W: Write> Game<W> {
+    /// 按读档得到的状态摆放格子,开始游戏时不再重新布置场景
+    fn restore_cells(&mut self) -> Result<(), GameError> {
+        let (width, height) = self.size();
+        let snack = std::iter::once(self.snack.head).chain(self.snack.bodys.iter().copied());
+        let initial = std::iter::once(self.initial_snack.head)
+            .chain(self.initial_snack.bodys.iter().copied());
+        let out_of_bounds = snack
+            .chain(initial)
+            .chain(self.foods.iter().map(|&(pos, _)| pos))
+            .chain(self.bonus.map(|(pos, _)| pos))
+            .chain(self.poison)
+            .chain(self.runner)
+            .chain(self.food_points.keys().copied())
+            .chain(self.portals.iter().flat_map(|&(a, b)| [a, b]))
+            .chain(self.shrink_pending.iter().copied())
+            .any(|(x, y)| x >= width || y >= height);
+        if out_of_bounds {
+            return Err(GameError::InvalidSave("坐标超出棋盘".to_string()));
+        }
+        for &(x, y) in self.map_walls.iter().flatten() {
+            self.cells[x][y].cell_type = CellType::Wall;
+        }
+        for &(x, y) in self.map_blades.iter() {
+            self.cells[x][y].cell_type = CellType::Blade;
+        }
+        for &(a, b) in self.portals.iter() {
+            for (x, y) in [a, b] {
+                self.cells[x][y].cell_type = CellType::Portal;
+            }
+        }
+        let (x, y) = self.snack.head;
+        self.cells[x][y].cell_type = CellType::SnackHead;
+        for &(x, y) in self.snack.bodys.iter() {
+            self.cells[x][y].cell_type = CellType::SnackBody;
+        }
+        for &((x, y), _) in self.foods.iter() {
+            self.cells[x][y].cell_type = CellType::Food;
+            let (h_x, h_y) = self.snack.head;
+            self.food_distances
+                .insert((x, y), x.abs_diff(h_x) + y.abs_diff(h_y));
+        }
+        if let Some(((x, y), _)) = self.bonus {
+            self.cells[x][y].cell_type = CellType::BonusFood;
+        }
+        if let Some((x, y)) = self.poison {
+            self.cells[x][y].cell_type = CellType::Poison;
+        }
+        if let Some((x, y)) = self.runner {
+            self.cells[x][y].cell_type = CellType::Runner;
+        }
+        self.loaded = true;
+        Ok(())
+    }
+}
+/// 分成键值和地图两部分
+fn split_save(text: &str) -> Result<(HashMap<&str, &str>, &str), GameError> {
+    let text = text
+        .strip_prefix(HEADER)
+        .ok_or_else(|| GameError::InvalidSave("不是存档文件".to_string()))?;
+    let (head, map) = text
+        .split_once(&format!("\n{}\n", MAP_LINE))
+        .ok_or_else(|| GameError::InvalidSave("缺少地图".to_string()))?;
+    let fields = head
+        .lines()
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(' '))
+        .collect();
+    Ok((fields, map))
+}
+/// `rng`一行:十六进制的种子、流编号、用掉的位置
+fn parse_rng(value: &str) -> Result<ChaCha12Rng, GameError> {
+    let error = || GameError::InvalidSave("rng格式错误".to_string());
+    let parts = value.split_whitespace().collect::<Vec<_>>();
+    let [seed, stream, word_pos] = parts[..] else {
+        return Err(error());
+    };
+    if seed.len() != 64 || !seed.is_ascii() {
+        return Err(error());
+    }
+    let mut bytes = [0; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&seed[i * 2..i * 2 + 2], 16).map_err(|_| error())?;
+    }
+    let mut rng = ChaCha12Rng::from_seed(bytes);
+    rng.set_stream(stream.parse().map_err(|_| error())?);
+    rng.set_word_pos(word_pos.parse().map_err(|_| error())?);
+    Ok(rng)
+}
+fn parse<T: std::str::FromStr>(value: &str, key: &str) -> Result<T, GameError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| GameError::InvalidSave(format!("{}格式错误", key)))
+}
+/// 空格分隔的一串数字
+fn numbers(value: &str, key: &str) -> Result<Vec<u64>, GameError> {
+    value.split_whitespace().map(|n| parse(n, key)).collect()
+}
+/// `-`表示没有
+fn optional<T>(
+    value: &str,
+    parse: impl Fn(&str) -> Option<T>,
+    key: &str,
+) -> Result<Option<T>, GameError> {
+    if value.trim() == "-" {
+        Ok(None)
+    } else {
+        parse(value.trim())
+            .map(Some)
+            .ok_or_else(|| GameError::InvalidSave(format!("{}格式错误", key)))
+    }
+}
+/// 上、下、左、右四条边
+pub(crate) fn border_to_string(border: BorderMode) -> String {
+    [border.top, border.bottom, border.left, border.right]
+        .map(|edge| match edge {
+            Edge::Wall => "wall",
+            Edge::Wrap => "wrap",
+        })
+        .join(" ")
+}
+pub(crate) fn parse_border(value: &str) -> Result<BorderMode, GameError> {
+    let edges = value
+        .split_whitespace()
+        .map(|edge| match edge {
+            "wall" => Ok(Edge::Wall),
+            "wrap" => Ok(Edge::Wrap),
+            _ => Err(GameError::InvalidSave("border格式错误".to_string())),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let [top, bottom, left, right] = edges[..] else {
+        return Err(GameError::InvalidSave("border格式错误".to_string()));
+    };
+    Ok(BorderMode {
+        top,
+        bottom,
+        left,
+        right,
+    })
+}
+pub(crate) fn pos_to_string((x, y): (usize, usize)) -> String {
+    format!("{},{}", x, y)
+}
+pub(crate) fn parse_pos(value: &str) -> Option<(usize, usize)> {
+    let (x, y) = value.split_once(',')?;
+    Some((x.parse().ok()?, y.parse().ok()?))
+}
+/// 朝向、上一帧移动方向、蛇头、蛇身
+pub(crate) fn snack_to_string(snack: &Snack) -> String {
+    std::iter::once(format!("{:?} {:?}", snack.direction, snack.moved))
+        .chain(
+            std::iter::once(snack.head)
+                .chain(snack.bodys.iter().copied())
+                .map(pos_to_string),
+        )
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+pub(crate) fn parse_snack(value: &str) -> Result<Snack, GameError> {
+    let error = || GameError::InvalidSave("snake格式错误".to_string());
+    let mut parts = value.split_whitespace();
+    let direction = parts.next().and_then(parse_direction).ok_or_else(error)?;
+    let moved = parts.next().and_then(parse_direction).ok_or_else(error)?;
+    let head = parts.next().and_then(parse_pos).ok_or_else(error)?;
+    let bodys = parts
+        .map(|pos| parse_pos(pos).ok_or_else(error))
+        .collect::<Result<_, _>>()?;
+    Ok(Snack {
+        direction,
+        moved,
+        head,
+        bodys,
+    })
+}
+fn parse_direction(value: &str) -> Option<Direction> {
+    match value {
+        "Left" => Some(Direction::Left),
+        "Right" => Some(Direction::Right),
+        "Up" => Some(Direction::Up),
+        "Down" => Some(Direction::Down),
+        _ => None,
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_save(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("r_snack_{}_{}.sav", name, std::process::id()))
+    }
+
+    #[test]
+    fn load_restores_saved_state_and_rng() {
+        let mut game = GameConfig::new()
+            .size(60, 20)
+            .seed(7)
+            .wrap_with_cost(2)
+            .food_values(1, 9)
+            .food_lifetime(200, 1)
+            .target_score(100)
+            .time_limit(Duration::from_secs(90))
+            .hazards(5)
+            .shrinking_arena(Duration::from_secs(20))
+            .build_with_writer(Vec::new())
+            .unwrap();
+        //十二对传送门,编号超过一位数
+        game.portals = (0..12).map(|i| ((4 + i * 4, 2), (4 + i * 4, 17))).collect();
+        game.build_default().unwrap();
+        //已经缩过一圈
+        game.stats.play_time = Duration::from_secs(25);
+        game.run_ticks(8, [Some(Direction::Up), None, Some(Direction::Left)])
+            .unwrap();
+        game.score = 12;
+        game.check_hazards();
+        game.combo = 3;
+        game.last_eat_tick = Some(game.ticks);
+        game.invuln_frames = 4;
+        game.shrink_pending.push(game.snack.head);
+        let path = temp_save("round_trip");
+        game.save(&path).unwrap();
+        let mut loaded = GameConfig::new()
+            .load_with_writer(&path, Vec::new())
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.portals.len(), 12);
+        assert_eq!((loaded.combo, loaded.last_eat_tick), (3, Some(game.ticks)));
+        assert_eq!(loaded.invuln_frames, 4);
+        assert_eq!(loaded.next_hazard, 15);
+        assert_eq!(loaded.shrink_depth, 1);
+        assert_eq!(loaded.next_shrink, Duration::from_secs(45));
+        assert_eq!(loaded.shrink_pending, vec![game.snack.head]);
+        assert_eq!(loaded.save_string(), game.save_string());
+        //随机数接着存档时的状态,之后生成的食物、分值、障碍墙和缩圈都一样
+        let inputs = [Some(Direction::Down), None, None, Some(Direction::Right)];
+        for input in inputs.into_iter().cycle().take(60) {
+            game.stats.play_time += Duration::from_secs(1);
+            loaded.stats.play_time += Duration::from_secs(1);
+            assert_eq!(loaded.step(input).unwrap(), game.step(input).unwrap());
+            assert_eq!(loaded.save_string(), game.save_string());
+        }
+    }
+
+    #[test]
+    fn two_player_and_levels_cannot_be_saved() {
+        let path = temp_save("unsupported");
+        let game = GameConfig::new()
+            .size(60, 20)
+            .two_player(true)
+            .build_headless()
+            .unwrap();
+        assert!(matches!(
+            game.save(&path),
+            Err(GameError::SaveUnsupported(_))
+        ));
+        assert!(!path.exists());
+        let autosave = GameConfig::new()
+            .levels(vec![crate::levels::builtin_level("cross")
+                .unwrap()
+                .to_string()])
+            .autosave(&path, Duration::from_secs(1))
+            .build_headless();
+        assert!(matches!(autosave, Err(GameError::SaveUnsupported(_))));
+    }
+}
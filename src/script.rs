@@ -0,0 +1,52 @@
+use std::io::{Sink, Write};
+
+use crate::{Direction, Game, GameConfig, GameError, GameOutcome, Stats};
+
+/// 脚本里的一步,用来写教程和测试
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum GameStep {
+    /// 转向,下一帧生效,和`Game::steer()`一样
+    Turn(Direction),
+    /// 推进一帧
+    Tick,
+    /// 在(x, y)放一个食物,和`Game::place_food_at()`一样
+    PlaceFood(usize, usize),
+    /// 连续推进几帧,期间不转向
+    Wait(u64),
+}
+impl<W: Write> Game<W> {
+    /// 按顺序执行脚本,转向和放食物在下一次推进之前生效,一局结束就停下,
+    /// 返回结束原因,脚本走完还没结束时是None。放食物的位置不对时返回错误
+    pub fn run_script(&mut self, script: &[GameStep]) -> Result<Option<GameOutcome>, GameError> {
+        for &step in script {
+            let ticks = match step {
+                GameStep::Turn(dir) => {
+                    self.steer(dir);
+                    0
+                }
+                GameStep::PlaceFood(x, y) => {
+                    self.place_food_at(x, y)?;
+                    0
+                }
+                GameStep::Tick => 1,
+                GameStep::Wait(frames) => frames,
+            };
+            for _ in 0..ticks {
+                let result = self.step(None)?;
+                if let Some(outcome) = self.game_over_cause(result) {
+                    return Ok(Some(outcome));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+impl Game {
+    /// 用默认配置和固定的种子0在不接终端的游戏里执行脚本,结果每次都一样,
+    /// 返回结束原因和本局统计
+    pub fn from_script(script: &[GameStep]) -> Result<(Option<GameOutcome>, Stats), GameError> {
+        let mut game: Game<Sink> = GameConfig::new().seed(0).build_headless()?;
+        let outcome = game.run_script(script)?;
+        Ok((outcome, game.game_stats().clone()))
+    }
+}
@@ -0,0 +1,85 @@
+use std::io::Write;
+
+use crate::{CellType, Game};
+
+/// 场地最少留下几格宽和高,再小就不缩了
+const SHRINK_MIN_INNER: usize = 4;
+
+impl<W: Write> Game<W> {
+    /// 开启缩圈时,到时间就把最外一圈空地变成墙
+    pub(crate) fn check_shrink(&mut self) {
+        //蛇身占着的格子等蛇走开再变成墙
+        let pending = std::mem::take(&mut self.shrink_pending);
+        for (x, y) in pending {
+            self.wall_ring_cell(x, y);
+        }
+        let Some(every) = self.shrink_every else {
+            return;
+        };
+        if self.stats.play_time >= self.next_shrink {
+            self.shrink_arena();
+            self.next_shrink = self.stats.play_time + every;
+        }
+    }
+    /// 重新开始时从最外面一圈重新缩
+    pub(crate) fn reset_shrink(&mut self) {
+        self.next_shrink = self.shrink_every.unwrap_or_default();
+        self.shrink_depth = 0;
+        self.shrink_pending.clear();
+    }
+    /// 从外往里找第一圈还有非墙格子的,整圈变成墙,场地太小了就不再缩
+    pub(crate) fn shrink_arena(&mut self) {
+        let (width, height) = self.size();
+        loop {
+            let depth = self.shrink_depth;
+            if width < 2 * (depth + 1) + SHRINK_MIN_INNER
+                || height < 2 * (depth + 1) + SHRINK_MIN_INNER
+            {
+                return;
+            }
+            self.shrink_depth += 1;
+            let ring = ring_cells(width, height, depth);
+            let all_walls = ring.iter().all(|&(x, y)| {
+                matches!(
+                    self.cells[x][y].cell_type,
+                    CellType::Wall | CellType::Portal
+                )
+            });
+            if all_walls {
+                continue;
+            }
+            for (x, y) in ring {
+                self.wall_ring_cell(x, y);
+            }
+            self.fill_food();
+            return;
+        }
+    }
+    /// 缩圈时一格变成墙,上面的食物去掉,蛇占着的记下来以后再说,传送门不动
+    fn wall_ring_cell(&mut self, x: usize, y: usize) {
+        match self.cells[x][y].cell_type {
+            CellType::Wall | CellType::Portal => return,
+            CellType::SnackHead
+            | CellType::SnackBody
+            | CellType::Snack2Head
+            | CellType::Snack2Body => {
+                self.shrink_pending.push((x, y));
+                return;
+            }
+            CellType::Food => self.remove_food((x, y)),
+            CellType::BonusFood => self.bonus = None,
+            CellType::Poison => self.poison = None,
+            CellType::Runner => self.runner = None,
+            _ => {}
+        }
+        self.set_cell(x, y, CellType::Wall);
+    }
+}
+/// 离边上depth格的那一圈格子
+fn ring_cells(width: usize, height: usize, depth: usize) -> Vec<(usize, usize)> {
+    let (left, right) = (depth, width - 1 - depth);
+    let (top, bottom) = (depth, height - 1 - depth);
+    let horizontal = (left..=right).flat_map(|x| [(x, top), (x, bottom)]);
+    let vertical = (top + 1..bottom).flat_map(|y| [(left, y), (right, y)]);
+    horizontal.chain(vertical).collect()
+}
@@ -0,0 +1,38 @@
+use std::{io, process, thread::JoinHandle};
+
+use signal_hook::{
+    consts::{SIGHUP, SIGINT, SIGTERM},
+    iterator::{Handle, Signals},
+};
+
+use crate::restore_terminal;
+
+/// 进程被外部终止时先恢复终端再退出,守卫销毁时取消,不影响游戏里按Esc退出
+pub(crate) struct SignalGuard {
+    handle: Handle,
+    thread: Option<JoinHandle<()>>,
+}
+impl SignalGuard {
+    pub fn install(alternate_screen: bool) -> io::Result<Self> {
+        let mut signals = Signals::new([SIGINT, SIGTERM, SIGHUP])?;
+        let handle = signals.handle();
+        let thread = std::thread::spawn(move || {
+            if let Some(signal) = signals.forever().next() {
+                restore_terminal(alternate_screen);
+                process::exit(128 + signal);
+            }
+        });
+        Ok(SignalGuard {
+            handle,
+            thread: Some(thread),
+        })
+    }
+}
+impl Drop for SignalGuard {
+    fn drop(&mut self) {
+        self.handle.close();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use std::collections::VecDeque;
+
+/// 食物放在哪里,设置后代替随机放置,方便测试和设计好的关卡
+pub trait FoodSpawner {
+    /// 从空格子里选一个放食物,empty不会是空的,返回的格子必须在empty里
+    fn place(&mut self, empty: &[(usize, usize)]) -> (usize, usize);
+}
+/// 总是放在第一个空格子,空格子按行从上到下、行内从左到右排列
+pub struct FirstEmpty;
+impl FoodSpawner for FirstEmpty {
+    fn place(&mut self, empty: &[(usize, usize)]) -> (usize, usize) {
+        empty[0]
+    }
+}
+/// 按预先写好的位置依次放,位置不是空格子或用完了就放在第一个空格子
+pub struct ScriptedFood {
+    positions: VecDeque<(usize, usize)>,
+}
+impl ScriptedFood {
+    pub fn new<I: IntoIterator<Item = (usize, usize)>>(positions: I) -> Self {
+        ScriptedFood {
+            positions: positions.into_iter().collect(),
+        }
+    }
+}
+impl FoodSpawner for ScriptedFood {
+    fn place(&mut self, empty: &[(usize, usize)]) -> (usize, usize) {
+        match self.positions.pop_front() {
+            Some(pos) if empty.contains(&pos) => pos,
+            _ => empty[0],
+        }
+    }
+}
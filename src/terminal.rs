@@ -0,0 +1,52 @@
+use std::io::{Result as IOResult, Write};
+
+use crossterm::{
+    cursor::Hide,
+    event::EnableFocusChange,
+    terminal::{
+        disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen, SetTitle,
+    },
+    QueueableCommand,
+};
+
+use crate::Game;
+
+/// 运行期间占用终端,进入原始模式和备用屏幕,离开作用域时恢复,出错提前返回时也一样
+pub(crate) struct Terminal<'a, W: Write> {
+    pub game: &'a mut Game<W>,
+    raw_mode: bool,
+}
+impl<'a, W: Write> Terminal<'a, W> {
+    pub fn enter(game: &'a mut Game<W>) -> IOResult<Self> {
+        //原始模式会禁用相关快捷键,不从键盘读输入时不需要
+        let raw_mode = game.input_source.is_none();
+        if raw_mode {
+            enable_raw_mode()?;
+        }
+        //先建好守卫,后面写入失败也会恢复
+        let terminal = Terminal { game, raw_mode };
+        terminal.game.in_alt_screen = true;
+        let writer = &mut terminal.game.writer;
+        //不用备用屏幕时清掉当前屏幕,原来的内容还在滚动记录里
+        if terminal.game.alternate_screen {
+            writer.queue(EnterAlternateScreen)?;
+        } else {
+            writer.queue(Clear(ClearType::All))?;
+        }
+        writer
+            .queue(SetTitle("Snack"))?
+            .queue(Hide)?
+            .queue(EnableFocusChange)?
+            .flush()?;
+        Ok(terminal)
+    }
+}
+impl<W: Write> Drop for Terminal<'_, W> {
+    fn drop(&mut self) {
+        //写入失败时也不能在drop中panic
+        let _ = self.game.leave_screen();
+        if self.raw_mode {
+            let _ = disable_raw_mode();
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crossterm::style::Color;
+
+use crate::CellType;
+
+/// 配色,每种格子一个颜色
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Theme {
+    pub wall: Color,
+    pub snack_head: Color,
+    pub snack_body: Color,
+    pub snack2_head: Color,
+    pub snack2_body: Color,
+    /// 单独显示蛇尾时蛇尾的颜色
+    pub snack_tail: Color,
+    pub food: Color,
+    pub bonus_food: Color,
+    pub blade: Color,
+    pub poison: Color,
+    pub portal: Color,
+    pub runner: Color,
+    pub empty: Color,
+    /// 所有格子的背景色
+    pub background: Color,
+}
+impl Theme {
+    /// 默认配色
+    pub fn classic() -> Self {
+        Theme {
+            wall: Color::Blue,
+            snack_head: Color::Green,
+            snack_body: Color::Yellow,
+            snack2_head: Color::Cyan,
+            snack2_body: Color::DarkCyan,
+            snack_tail: Color::DarkYellow,
+            food: Color::Red,
+            bonus_food: Color::Magenta,
+            blade: Color::Grey,
+            poison: Color::Cyan,
+            portal: Color::DarkMagenta,
+            runner: Color::DarkYellow,
+            empty: Color::Black,
+            background: Color::Black,
+        }
+    }
+    /// 黑白配色
+    pub fn monochrome() -> Self {
+        Theme {
+            wall: Color::White,
+            snack_head: Color::White,
+            snack_body: Color::Grey,
+            snack2_head: Color::White,
+            snack2_body: Color::DarkGrey,
+            snack_tail: Color::DarkGrey,
+            food: Color::White,
+            bonus_food: Color::Grey,
+            blade: Color::White,
+            poison: Color::DarkGrey,
+            portal: Color::White,
+            runner: Color::Grey,
+            empty: Color::Black,
+            background: Color::Black,
+        }
+    }
+    /// 高对比度,配合`Glyphs::ascii()`色弱也能靠字符形状分辨蛇头、蛇身和食物
+    pub fn high_contrast() -> Self {
+        Theme {
+            wall: Color::White,
+            snack_head: Color::Yellow,
+            snack_body: Color::Cyan,
+            snack2_head: Color::Green,
+            snack2_body: Color::Blue,
+            snack_tail: Color::DarkCyan,
+            food: Color::Red,
+            bonus_food: Color::Magenta,
+            blade: Color::Red,
+            poison: Color::Green,
+            portal: Color::Blue,
+            runner: Color::DarkRed,
+            empty: Color::Black,
+            background: Color::Black,
+        }
+    }
+    /// 格子类型对应的颜色
+    pub fn color(&self, cell_type: CellType) -> Color {
+        match cell_type {
+            CellType::Wall => self.wall,
+            CellType::SnackHead => self.snack_head,
+            CellType::SnackBody => self.snack_body,
+            CellType::Snack2Head => self.snack2_head,
+            CellType::Snack2Body => self.snack2_body,
+            CellType::Food => self.food,
+            CellType::BonusFood => self.bonus_food,
+            CellType::Blade => self.blade,
+            CellType::Poison => self.poison,
+            CellType::Portal => self.portal,
+            CellType::Runner => self.runner,
+            CellType::Empty => self.empty,
+        }
+    }
+}
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::classic()
+    }
+}
@@ -0,0 +1,95 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use r_snack::{Direction, FirstEmpty, GameConfig, StepResult};
+
+/// 数一下当前线程分配了几次内存,测试是并行跑的,各算各的
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+fn config() -> GameConfig {
+    GameConfig::new()
+        .size(60, 20)
+        .seed(5)
+        .initial_snake((20, 10), 3, Direction::Right)
+}
+
+/// 先走一帧再吃掉正前方的食物,返回吃的那一帧分配了几次内存
+fn eat_allocations(config: GameConfig) -> usize {
+    let mut game = config.build_headless().unwrap();
+    game.food_spawner(FirstEmpty);
+    assert_eq!(game.step(None).unwrap(), StepResult::Moved);
+    game.place_food_at(22, 10).unwrap();
+    let before = allocations();
+    assert_eq!(game.step(None).unwrap(), StepResult::AteFood);
+    allocations() - before
+}
+
+#[test]
+fn spawning_bonus_poison_and_hazards_does_not_allocate() {
+    //分数栏的字符串两边一样多,多出来的只能是放奖励食物、毒药和障碍时分配的
+    let plain = eat_allocations(config().bonus_chance(0.0));
+    let spawning = eat_allocations(config().bonus_chance(1.0).hazards(1));
+    assert_eq!(spawning, plain);
+}
+
+#[test]
+fn autopilot_and_runner_do_not_allocate() {
+    let mut game = config()
+        .bonus_chance(1.0)
+        .runner_food(1)
+        .build_headless()
+        .unwrap();
+    let mut plain = config().build_headless().unwrap();
+    for _ in 0..20 {
+        let before = allocations();
+        game.ai_next_direction();
+        assert_eq!(allocations(), before);
+        //会跑的食物每帧都挪,和没有它的一局分配的次数一样
+        let before = allocations();
+        assert_eq!(game.step(None).unwrap(), StepResult::Moved);
+        let with_runner = allocations() - before;
+        let before = allocations();
+        assert_eq!(plain.step(None).unwrap(), StepResult::Moved);
+        assert_eq!(with_runner, allocations() - before);
+    }
+}
+
+#[test]
+fn moving_without_eating_does_not_allocate() {
+    //食物过几帧就烂掉重新放,不扣分,分数栏不用重画
+    let mut game = config().food_lifetime(5, 0).build_headless().unwrap();
+    game.food_spawner(FirstEmpty);
+    //前几帧让缓冲区长到稳定的大小
+    for _ in 0..6 {
+        assert_eq!(game.step(None).unwrap(), StepResult::Moved);
+    }
+    let before = allocations();
+    for _ in 0..10 {
+        assert_eq!(game.step(None).unwrap(), StepResult::Moved);
+    }
+    assert!(game.run_ticks(10, None).unwrap().is_none());
+    assert_eq!(allocations(), before);
+}
@@ -0,0 +1,45 @@
+use std::io::{self, Write};
+
+use r_snack::{Direction, GameConfig, GameError, ScriptedInput};
+
+/// 写够limit字节以后就像管道被关掉一样报错
+struct FailingWriter {
+    written: usize,
+    limit: usize,
+}
+impl Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written >= self.limit {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"));
+        }
+        let n = buf.len().min(self.limit - self.written);
+        self.written += n;
+        Ok(n)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        if self.written >= self.limit {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"));
+        }
+        Ok(())
+    }
+}
+
+fn run_with_limit(limit: usize) -> Result<(), GameError> {
+    let mut game = GameConfig::new()
+        .size(60, 20)
+        .seed(1)
+        .build_with_writer(FailingWriter { written: 0, limit })?;
+    game.input_source(ScriptedInput::new(vec![Some(Direction::Down); 50]));
+    game.run().map(|_| ())
+}
+
+#[test]
+fn closed_writer_ends_game_with_io_error() {
+    //开局、第一帧和之后的渲染中途断开都要返回错误而不是panic
+    for limit in [0, 10, 500, 5_000] {
+        match run_with_limit(limit) {
+            Err(GameError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::BrokenPipe),
+            other => panic!("limit {}: {:?}", limit, other),
+        }
+    }
+}